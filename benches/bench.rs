@@ -37,6 +37,11 @@ fn simple_match(c: &mut Criterion) {
     b.iter(|| assert!(fast_glob::glob_match(GLOB, PATH)))
   });
 
+  group.bench_function("fast-glob-pre-compiled", |b| {
+    let matcher = fast_glob::Glob::new(GLOB).unwrap();
+    b.iter(|| assert!(matcher.is_match(PATH)))
+  });
+
   group.finish();
 }
 
@@ -68,8 +73,41 @@ fn brace_expansion(c: &mut Criterion) {
     b.iter(|| assert!(fast_glob::glob_match(GLOB, PATH)));
   });
 
+  group.bench_function("fast-glob-pre-compiled", |b| {
+    let matcher = fast_glob::Glob::new(GLOB).unwrap();
+    b.iter(|| assert!(matcher.is_match(PATH)))
+  });
+
+  group.finish();
+}
+
+fn multi_match(c: &mut Criterion) {
+  let mut group = c.benchmark_group("multi_match");
+
+  const GLOBS: [&str; 4] = [
+    "src/**/*.{ts,tsx}",
+    "src/**/test/*.rs",
+    "src/vs/**/n*d[k-m]e?txt",
+    "**/*.md",
+  ];
+  const PATH: &str = "src/vs/a/bigger/path/to/the/crazy/needle.txt";
+
+  group.bench_function("globset-pre-compiled", |b| {
+    let mut builder = globset::GlobSetBuilder::new();
+    for glob in GLOBS {
+      builder.add(globset::Glob::new(glob).unwrap());
+    }
+    let matcher = builder.build().unwrap();
+    b.iter(|| assert_eq!(matcher.matches(PATH), vec![2]))
+  });
+
+  group.bench_function("fast-glob-pre-compiled", |b| {
+    let matcher = fast_glob::GlobSet::new(GLOBS).unwrap();
+    b.iter(|| assert_eq!(matcher.matches(PATH), vec![2]))
+  });
+
   group.finish();
 }
 
-criterion_group!(benches, simple_match, brace_expansion);
+criterion_group!(benches, simple_match, brace_expansion, multi_match);
 criterion_main!(benches);
@@ -0,0 +1,933 @@
+/**
+ * The following code is modified based on
+ * https://github.com/devongovett/glob-match/blob/d5a6c67/src/lib.rs
+ *
+ * MIT Licensed
+ * Copyright (c) 2023 Devon Govett
+ * https://github.com/devongovett/glob-match/tree/main/LICENSE
+ */
+use std::{cell::Cell, collections::HashSet, mem};
+
+use arrayvec::ArrayVec;
+
+use crate::{
+  glob::{Capture, DescendantMatch, ExtglobKind, Glob, Sequence, Token},
+  MatchOptions,
+};
+
+/// Marks a token that doesn't record a capture.
+pub(crate) const NO_CAPTURE: u32 = u32::MAX;
+
+/// The tokens of a compiled glob, along with what the engine should record while
+/// matching them.
+#[derive(Clone, Copy)]
+pub(crate) struct Program<'a> {
+  pub(crate) tokens: &'a [Token],
+  /// The capture each token records into, or [`NO_CAPTURE`]. Only read by the engine
+  /// when it is instantiated with `CAPTURE` set.
+  pub(crate) slots: &'a [u32],
+  pub(crate) sequences: &'a [Sequence],
+  /// Whether wildcards step over whole UTF-8 characters rather than single bytes.
+  pub(crate) utf8: bool,
+  /// The bytes separating path segments, which may be the same byte twice.
+  pub(crate) separators: [u8; 2],
+  /// Whether `**` matches path segments starting with a dot.
+  pub(crate) dot: bool,
+  /// Whether `*` and `?` stop at separators.
+  pub(crate) literal_separator: bool,
+  /// Set when matching a directory against the start of the glob. The path then matches
+  /// once the glob is known to match everything under it, and the cell records whether
+  /// the path was ever consumed at all.
+  pub(crate) partial: Option<&'a Cell<bool>>,
+}
+
+impl<'a> Program<'a> {
+  fn new(glob: &'a Glob, slots: &'a [u32]) -> Program<'a> {
+    Program {
+      slots,
+      ..Program::from_tokens(&glob.tokens, &glob.sequences, &glob.options)
+    }
+  }
+
+  fn from_tokens(
+    tokens: &'a [Token],
+    sequences: &'a [Sequence],
+    options: &MatchOptions,
+  ) -> Program<'a> {
+    Program {
+      tokens,
+      slots: &[],
+      sequences,
+      utf8: options.utf8,
+      separators: options.separator.bytes(),
+      dot: options.dot,
+      literal_separator: options.literal_separator,
+      partial: None,
+    }
+  }
+
+  #[inline(always)]
+  fn is_separator(&self, c: u8) -> bool {
+    c == self.separators[0] || c == self.separators[1]
+  }
+
+  /// Returns whether `c` is a separator that wildcards can't match.
+  #[inline(always)]
+  fn is_literal_separator(&self, c: u8) -> bool {
+    self.literal_separator && self.is_separator(c)
+  }
+
+  /// Returns whether `tokens[glob_index..]` match any path at all, which is the case when
+  /// all that is left is a trailing `**` and the braces around it.
+  fn matches_everything(&self, mut glob_index: usize) -> bool {
+    let mut globstar = false;
+    while glob_index < self.tokens.len() {
+      match self.tokens[glob_index] {
+        Token::Globstar { end: true } => globstar = true,
+        Token::Branch { end, .. } => {
+          glob_index = end as usize;
+          continue;
+        }
+        Token::Close => {}
+        _ => return false,
+      }
+      glob_index += 1;
+    }
+
+    globstar && self.dot
+  }
+
+  /// Returns whether `path[path_index]` is a dot starting a path segment.
+  #[inline(always)]
+  fn is_leading_dot(&self, path: &[u8], path_index: usize) -> bool {
+    path_index < path.len()
+      && path[path_index] == b'.'
+      && (path_index == 0 || self.is_separator(path[path_index - 1]))
+  }
+
+  /// Returns whether `*` can't match the character at `path_index`, being a separator that
+  /// wildcards can't match, or a leading dot when wildcards don't match those.
+  #[inline(always)]
+  fn stops_star(&self, path: &[u8], path_index: usize) -> bool {
+    path_index < path.len()
+      && (self.is_literal_separator(path[path_index])
+        || (!self.dot && self.is_leading_dot(path, path_index)))
+  }
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct State {
+  path_index: usize,
+  glob_index: usize,
+
+  wildcard: Wildcard,
+  globstar: Wildcard,
+
+  captures: Vec<Capture>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Wildcard {
+  glob_index: u32,
+  path_index: u32,
+}
+
+/// The positions in the glob and the path from which the rest of the glob failed to match,
+/// without backtracking into wildcards ahead of them, remembered once the engine got there
+/// many times. Each brace and extglob group tries the rest of the glob after each of its
+/// branches or ends, which takes exponential time in the number of groups unless the
+/// failures are remembered.
+#[derive(Default)]
+struct Failures {
+  /// How many times the rest of the glob was matched from a group so far.
+  attempts: usize,
+  positions: HashSet<(u32, u32)>,
+}
+
+impl Failures {
+  /// Past this many attempts, failures are remembered. Most matches never get there, and
+  /// so never allocate.
+  const MEMOIZE_AFTER: usize = 256;
+
+  /// Returns whether matching from `glob_index` and `path_index` is known to fail.
+  #[inline(always)]
+  fn contains(&mut self, glob_index: usize, path_index: usize) -> bool {
+    self.attempts += 1;
+    self.attempts > Self::MEMOIZE_AFTER
+      && self
+        .positions
+        .contains(&(glob_index as u32, path_index as u32))
+  }
+
+  #[inline(always)]
+  fn insert(&mut self, glob_index: usize, path_index: usize) {
+    if self.attempts > Self::MEMOIZE_AFTER {
+      self
+        .positions
+        .insert((glob_index as u32, path_index as u32));
+    }
+  }
+}
+
+pub(crate) fn match_glob(glob: &Glob, path: &[u8]) -> bool {
+  match_glob_from(glob, &path[glob.match_start(path)..], 0, 0)
+}
+
+/// Matches the tokens of a pattern that isn't matched against basenames against `path`.
+pub(crate) fn match_tokens(
+  tokens: &[Token],
+  sequences: &[Sequence],
+  options: &MatchOptions,
+  path: &[u8],
+) -> bool {
+  let program = Program::from_tokens(tokens, sequences, options);
+  let mut failures = Failures::default();
+  State::default().glob_match_from::<false>(program, path, &mut failures)
+}
+
+/// Matches the directory `dir`, which ends with a separator unless it is empty, against
+/// the start of `glob`.
+pub(crate) fn match_descendants(glob: &Glob, dir: &[u8]) -> DescendantMatch {
+  let consumed = Cell::new(false);
+  let program = Program {
+    partial: Some(&consumed),
+    ..Program::new(glob, &[])
+  };
+
+  let mut failures = Failures::default();
+  if State::default().glob_match_from::<false>(program, dir, &mut failures) {
+    DescendantMatch::Yes
+  } else if consumed.get() {
+    DescendantMatch::Maybe
+  } else {
+    DescendantMatch::No
+  }
+}
+
+/// Matches `glob.tokens[glob_index..]` against `path[path_index..]`, where the tokens
+/// before `glob_index` are literals already known to match the path before `path_index`.
+pub(crate) fn match_glob_from(
+  glob: &Glob,
+  path: &[u8],
+  glob_index: usize,
+  path_index: usize,
+) -> bool {
+  let program = Program::new(glob, &[]);
+  let mut state = State {
+    path_index,
+    glob_index,
+    ..State::default()
+  };
+
+  let mut failures = Failures::default();
+  state.glob_match_from::<false>(program, path, &mut failures)
+}
+
+/// Matches `glob` against `path`, returning the `count` captures described by `slots`.
+pub(crate) fn match_captures(
+  glob: &Glob,
+  slots: &[u32],
+  count: usize,
+  path: &[u8],
+) -> Option<Vec<Capture>> {
+  let program = Program::new(glob, slots);
+  let mut state = State {
+    captures: vec![Capture::default(); count],
+    ..State::default()
+  };
+
+  let mut failures = Failures::default();
+  if state.glob_match_from::<true>(program, path, &mut failures) {
+    Some(state.captures)
+  } else {
+    None
+  }
+}
+
+/// Returns whether the literal `token` matches the path byte `c`, where `separators` are
+/// the bytes matched by [`Token::Separator`].
+#[inline(always)]
+pub(crate) fn match_literal(token: Token, c: u8, separators: [u8; 2]) -> bool {
+  match token {
+    Token::Byte(b) => b == c,
+    Token::Caseless(b) => b == c.to_ascii_lowercase(),
+    Token::Separator => c == separators[0] || c == separators[1],
+    _ => false,
+  }
+}
+
+/// Decodes the UTF-8 character at the start of `bytes`, returning it with its length.
+#[inline(always)]
+pub(crate) fn decode_char(bytes: &[u8]) -> Option<(char, usize)> {
+  let len = match *bytes.first()? {
+    0x00..=0x7f => 1,
+    0xc0..=0xdf => 2,
+    0xe0..=0xef => 3,
+    0xf0..=0xf7 => 4,
+    _ => return None,
+  };
+
+  let c = std::str::from_utf8(bytes.get(..len)?)
+    .ok()?
+    .chars()
+    .next()?;
+  Some((c, len))
+}
+
+/// Decodes the character at the start of a non-empty `bytes`, reading a byte that isn't
+/// valid UTF-8 as a U+FFFD replacement character of length 1.
+#[inline(always)]
+pub(crate) fn decode_char_lossy(bytes: &[u8]) -> (char, usize) {
+  decode_char(bytes).unwrap_or((char::REPLACEMENT_CHARACTER, 1))
+}
+
+/// Returns whether `c` is in one of the `ranges` of a class, where `caseless` classes
+/// also accept the single-character case mappings of a non-ASCII `c`. ASCII letters are
+/// already covered by the ranges themselves.
+#[inline(always)]
+fn match_ranges(ranges: &[Token], c: char, caseless: bool) -> bool {
+  let in_ranges = |c: char| {
+    ranges
+      .iter()
+      .any(|range| matches!(*range, Token::Range(low, high) if low <= c as u32 && c as u32 <= high))
+  };
+
+  if in_ranges(c) {
+    return true;
+  }
+
+  if caseless && !c.is_ascii() {
+    let mut lower = c.to_lowercase();
+    let mut upper = c.to_uppercase();
+    return matches!((lower.next(), lower.next()), (Some(l), None) if l != c && in_ranges(l))
+      || matches!((upper.next(), upper.next()), (Some(u), None) if u != c && in_ranges(u));
+  }
+
+  false
+}
+
+/// Returns whether the token at `glob_index` can't match a path continuing with `c`, which
+/// is only worked out for literals and braces whose branches start with one.
+#[inline(always)]
+fn rules_out(tokens: &[Token], glob_index: usize, c: u8) -> bool {
+  match tokens.get(glob_index) {
+    Some(Token::Byte(b)) => *b != c,
+    Some(Token::Open { .. }) => rules_out_branches(tokens, glob_index, c),
+    _ => false,
+  }
+}
+
+/// Returns whether every branch of the brace opened at `glob_index` starts with a literal
+/// other than `c`, so entering it would fail right away.
+#[inline(never)]
+fn rules_out_branches(tokens: &[Token], glob_index: usize, c: u8) -> bool {
+  let starts_otherwise = |index: usize| matches!(tokens[index], Token::Byte(b) if b != c);
+  let Token::Open { mut next } = tokens[glob_index] else {
+    return false;
+  };
+
+  if !starts_otherwise(glob_index + 1) {
+    return false;
+  }
+  while let Token::Branch { next: n, .. } = tokens[next as usize] {
+    if !starts_otherwise(next as usize + 1) {
+      return false;
+    }
+    next = n;
+  }
+  true
+}
+
+/// Returns the end positions of the values of `sequence` found at `path[path_index..]`,
+/// in ascending order.
+fn sequence_ends(sequence: &Sequence, path: &[u8], path_index: usize) -> ArrayVec<usize, 20> {
+  let mut ends = ArrayVec::new();
+  let rest = &path[path_index..];
+
+  if sequence.chars {
+    if let Some(&c) = rest.first() {
+      if sequence.contains(c as i64)
+        || (sequence.caseless && c.is_ascii_alphabetic() && sequence.contains((c ^ 0x20) as i64))
+      {
+        ends.push(path_index + 1);
+      }
+    }
+    return ends;
+  }
+
+  let sign = rest.first().is_some_and(|&c| c == b'-') as usize;
+  let digits = rest[sign..]
+    .iter()
+    .take(18)
+    .take_while(|c| c.is_ascii_digit())
+    .count();
+
+  for len in sign + 1..=sign + digits {
+    let value = rest[sign..len]
+      .iter()
+      .fold(0, |value, &c| value * 10 + (c - b'0') as i64);
+    let value = if sign == 1 { -value } else { value };
+
+    // Only the spelling the sequence expands to matches, with exactly its padding.
+    let natural_len =
+      (value < 0) as usize + value.unsigned_abs().checked_ilog10().unwrap_or(0) as usize + 1;
+    if sequence.contains(value)
+      && (sign == 1) == (value < 0)
+      && len == natural_len.max(sequence.width)
+    {
+      ends.push(path_index + len);
+    }
+  }
+
+  ends
+}
+
+/// Maps `c` to a canonical case, so that two characters differing only in case fold to
+/// the same one. Characters whose case mapping isn't a single character are kept as is.
+pub(crate) fn fold_case(c: char) -> char {
+  fn single(mut chars: impl Iterator<Item = char>, c: char) -> char {
+    match (chars.next(), chars.next()) {
+      (Some(mapped), None) => mapped,
+      _ => c,
+    }
+  }
+
+  let upper = single(c.to_uppercase(), c);
+  single(upper.to_lowercase(), upper)
+}
+
+impl State {
+  #[inline(always)]
+  fn backtrack(&mut self) {
+    self.glob_index = self.wildcard.glob_index as usize;
+    self.path_index = self.wildcard.path_index as usize;
+  }
+
+  /// Returns whether the wildcard at the current position is being retried after a
+  /// backtrack, rather than reached for the first time.
+  #[inline(always)]
+  fn is_resumed(&self) -> bool {
+    self.wildcard.path_index > 0
+      && self.wildcard.glob_index as usize == self.glob_index
+      && self.wildcard.path_index as usize == self.path_index
+  }
+
+  /// Returns whether a star ahead of the class at the current position, matching what
+  /// `is_match` accepts, can stretch for the class to match a separator. A star past the
+  /// class can't stand in for it then, so the rest of the glob is matched on its own.
+  #[inline(always)]
+  fn is_stretchable(&self, program: Program, is_match: impl Fn(u8) -> bool) -> bool {
+    self.wildcard != self.globstar
+      && [b'/', b'\\']
+        .into_iter()
+        .any(|c| program.is_literal_separator(c) && is_match(c))
+  }
+
+  /// Records that the token at the current position matched `path[start..end]`, or if
+  /// `start` is `None`, that the capture it is part of now ends at `end`.
+  #[inline(always)]
+  fn capture<const CAPTURE: bool>(&mut self, program: Program, start: Option<usize>, end: usize) {
+    if !CAPTURE {
+      return;
+    }
+
+    let slot = program.slots[self.glob_index];
+    if slot != NO_CAPTURE {
+      let range = &mut self.captures[slot as usize].range;
+      if let Some(start) = start {
+        range.start = start;
+      }
+      range.end = end;
+    }
+  }
+
+  #[inline(always)]
+  fn skip_to_separator(&mut self, program: Program, path: &[u8], is_end_invalid: bool) {
+    if self.path_index == path.len() {
+      self.wildcard.path_index += 1;
+      return;
+    }
+
+    let mut path_index = self.path_index;
+    while path_index < path.len() && !program.is_separator(path[path_index]) {
+      path_index += 1;
+    }
+
+    if is_end_invalid || path_index != path.len() {
+      path_index += 1;
+    }
+
+    self.wildcard.path_index = path_index as u32;
+    self.globstar = self.wildcard;
+  }
+
+  /// Matches the run of literal bytes and separators at the current position, returning
+  /// whether any of it matched.
+  #[inline(always)]
+  fn match_literals(&mut self, program: Program, path: &[u8]) -> bool {
+    let start = self.path_index;
+    loop {
+      match (
+        program.tokens.get(self.glob_index),
+        path.get(self.path_index),
+      ) {
+        (Some(&Token::Byte(c)), Some(&d)) if c == d => {}
+        (Some(Token::Separator), Some(&d)) if program.is_separator(d) => {
+          if program.literal_separator {
+            self.wildcard = self.globstar;
+          }
+        }
+        _ => return self.path_index != start,
+      }
+      self.glob_index += 1;
+      self.path_index += 1;
+    }
+  }
+
+  fn match_brace_branch<const CAPTURE: bool>(
+    &mut self,
+    program: Program,
+    path: &[u8],
+    open_brace_index: usize,
+    (branch, branch_index): (usize, usize),
+    failures: &mut Failures,
+  ) -> bool {
+    let mut branch_state = State {
+      glob_index: branch_index,
+      captures: if CAPTURE {
+        self.captures.clone()
+      } else {
+        Vec::new()
+      },
+      path_index: self.path_index,
+      ..State::default()
+    };
+
+    if CAPTURE && program.slots[open_brace_index] != NO_CAPTURE {
+      let capture = &mut branch_state.captures[program.slots[open_brace_index] as usize];
+      capture.range = self.path_index..self.path_index;
+      capture.branch = Some(branch);
+    }
+
+    let matched = branch_state.glob_match_from::<CAPTURE>(program, path, failures);
+    if matched {
+      self.captures = mem::take(&mut branch_state.captures);
+    }
+
+    matched
+  }
+
+  /// Returns the end positions `self.path_index..=limit` at which the extglob group opened
+  /// at the current position can stop matching, in ascending order.
+  fn extglob_ends(
+    &self,
+    program: Program,
+    path: &[u8],
+    kind: ExtglobKind,
+    mut next: usize,
+    limit: usize,
+  ) -> Vec<usize> {
+    // The alternatives of the group, as the index of their first token and of the branch
+    // separator or close that ends them.
+    let mut alternatives = vec![(self.glob_index + 1, next)];
+    while let Token::Branch { next: n, .. } = program.tokens[next] {
+      alternatives.push((next + 1, n as usize));
+      next = n as usize;
+    }
+
+    let is_boundary =
+      |index: usize| !program.utf8 || index == path.len() || !matches!(path[index], 0x80..=0xbf);
+
+    // Whether one of the alternatives matches exactly `path[start..end]`.
+    let matches = |start: usize, end: usize| {
+      alternatives.iter().any(|&(glob_index, terminator)| {
+        let alternative = Program {
+          tokens: &program.tokens[..terminator],
+          slots: &[],
+          partial: None,
+          ..program
+        };
+        let mut state = State {
+          path_index: start,
+          glob_index,
+          ..State::default()
+        };
+        state.glob_match_from::<false>(alternative, &path[..end], &mut Failures::default())
+      })
+    };
+
+    let start = self.path_index;
+    let ends = start..=limit;
+    match kind {
+      ExtglobKind::One => ends
+        .filter(|&end| is_boundary(end) && matches(start, end))
+        .collect(),
+      ExtglobKind::ZeroOrOne => ends
+        .filter(|&end| is_boundary(end) && (end == start || matches(start, end)))
+        .collect(),
+      ExtglobKind::Not => ends
+        .filter(|&end| is_boundary(end) && !matches(start, end))
+        .collect(),
+      ExtglobKind::ZeroOrMore | ExtglobKind::OneOrMore => {
+        // Whether each position is reached by a run of repetitions from the start.
+        let mut reached = vec![false; limit - start + 1];
+        reached[0] = kind == ExtglobKind::ZeroOrMore || matches(start, start);
+        let mut from = vec![start];
+        while let Some(position) = from.pop() {
+          for end in position + 1..=limit {
+            if !reached[end - start] && is_boundary(end) && matches(position, end) {
+              reached[end - start] = true;
+              from.push(end);
+            }
+          }
+        }
+
+        ends.filter(|&end| reached[end - start]).collect()
+      }
+    }
+  }
+
+  /// Matches the extglob group at the current position, followed by the rest of the glob.
+  fn match_extglob<const CAPTURE: bool>(
+    &mut self,
+    program: Program,
+    path: &[u8],
+    failures: &mut Failures,
+  ) -> bool {
+    let Token::Extglob {
+      kind,
+      multi_segment,
+      next,
+      end,
+    } = program.tokens[self.glob_index]
+    else {
+      unreachable!()
+    };
+
+    if let Some(consumed) = program.partial.filter(|_| multi_segment) {
+      // The group may go on past the end of the directory.
+      consumed.set(true);
+    }
+
+    let limit = if multi_segment || !program.literal_separator {
+      path.len()
+    } else {
+      path[self.path_index..]
+        .iter()
+        .position(|&c| program.is_separator(c))
+        .map_or(path.len(), |len| self.path_index + len)
+    };
+
+    let ends = self.extglob_ends(program, path, kind, next as usize, limit);
+    self.match_ends::<CAPTURE>(program, path, &ends, end as usize, failures)
+  }
+
+  /// Matches the rest of the glob from `glob_index` after the token at the current
+  /// position has matched up to one of the `ends`.
+  fn match_ends<const CAPTURE: bool>(
+    &mut self,
+    program: Program,
+    path: &[u8],
+    ends: &[usize],
+    glob_index: usize,
+    failures: &mut Failures,
+  ) -> bool {
+    for &path_index in ends {
+      if failures.contains(glob_index, path_index) {
+        continue;
+      }
+
+      // Backtracking into a wildcard before the token is left to the caller, which
+      // tries the token again from the new position.
+      let mut state = State {
+        path_index,
+        glob_index,
+        captures: mem::take(&mut self.captures),
+        ..State::default()
+      };
+      if CAPTURE && program.slots[self.glob_index] != NO_CAPTURE {
+        let capture = &mut state.captures[program.slots[self.glob_index] as usize];
+        capture.range = self.path_index..path_index;
+        capture.branch = None;
+      }
+
+      let matched = state.glob_match_from::<CAPTURE>(program, path, failures);
+      self.captures = state.captures;
+      if matched {
+        return true;
+      }
+      failures.insert(glob_index, path_index);
+    }
+
+    false
+  }
+
+  fn match_brace<const CAPTURE: bool>(
+    &mut self,
+    program: Program,
+    path: &[u8],
+    mut next: usize,
+    failures: &mut Failures,
+  ) -> bool {
+    let open_brace_index = self.glob_index;
+    if failures.contains(open_brace_index, self.path_index) {
+      return false;
+    }
+
+    let mut branch = (0, open_brace_index + 1);
+    loop {
+      if self.match_brace_branch::<CAPTURE>(program, path, open_brace_index, branch, failures) {
+        return true;
+      }
+
+      match program.tokens[next] {
+        Token::Branch { next: n, .. } => {
+          branch = (branch.0 + 1, next + 1);
+          next = n as usize;
+        }
+        _ => break,
+      }
+    }
+
+    failures.insert(open_brace_index, self.path_index);
+    false
+  }
+
+  #[inline(always)]
+  fn glob_match_from<const CAPTURE: bool>(
+    &mut self,
+    program: Program,
+    path: &[u8],
+    failures: &mut Failures,
+  ) -> bool {
+    let tokens = program.tokens;
+    loop {
+      if self.path_index == path.len() {
+        if let Some(consumed) = program.partial {
+          // The whole directory is consumed, so whatever follows might match its contents.
+          if program.matches_everything(self.glob_index) {
+            return true;
+          }
+          consumed.set(true);
+        } else if self.glob_index == tokens.len() {
+          return true;
+        }
+      }
+
+      if self.glob_index < tokens.len() {
+        match tokens[self.glob_index] {
+          Token::Star => {
+            if CAPTURE {
+              let start = (!self.is_resumed()).then_some(self.path_index);
+              self.capture::<CAPTURE>(program, start, self.path_index);
+            }
+
+            let step = if program.utf8 && self.path_index < path.len() {
+              decode_char_lossy(&path[self.path_index..]).1
+            } else {
+              1
+            };
+
+            self.wildcard.glob_index = self.glob_index as u32;
+            self.wildcard.path_index = (self.path_index + step) as u32;
+            self.glob_index += 1;
+
+            if program.stops_star(path, self.path_index) {
+              self.wildcard = self.globstar;
+            }
+
+            // The star stretches up to where the token following it could match, the way
+            // backtracking into it would.
+            while let (false, Some(&c)) = (CAPTURE, path.get(self.path_index)) {
+              if !rules_out(tokens, self.glob_index, c) || program.stops_star(path, self.path_index)
+              {
+                break;
+              }
+
+              self.path_index = self.wildcard.path_index as usize;
+              let step = if program.utf8 && self.path_index < path.len() {
+                decode_char_lossy(&path[self.path_index..]).1
+              } else {
+                1
+              };
+              self.wildcard.path_index = (self.path_index + step) as u32;
+
+              if program.stops_star(path, self.path_index) {
+                self.wildcard = self.globstar;
+              }
+            }
+
+            continue;
+          }
+          Token::Globstar { end } => {
+            if CAPTURE {
+              let start = (!self.is_resumed()).then_some(self.path_index);
+              let end = match start {
+                None if program.is_separator(path[self.path_index - 1]) => self.path_index - 1,
+                _ => self.path_index,
+              };
+              self.capture::<CAPTURE>(program, start, end);
+            }
+
+            self.wildcard.glob_index = self.glob_index as u32;
+            self.wildcard.path_index = self.path_index as u32 + 1;
+            self.glob_index += 1;
+
+            if !program.dot && program.is_leading_dot(path, self.path_index) {
+              // The globstar can't span this segment, so it can't be retried further on.
+              self.wildcard.path_index = path.len() as u32 + 1;
+              self.globstar = self.wildcard;
+              continue;
+            }
+
+            self.skip_to_separator(program, path, !end);
+
+            // The token following the globstar can't match at a segment starting with the
+            // wrong literal, so the globstar moves on to the next segment right away, the
+            // way backtracking into it would.
+            while let (false, Some(&c)) = (CAPTURE, path.get(self.path_index)) {
+              if !rules_out(tokens, self.glob_index, c)
+                || self.wildcard.path_index as usize > path.len()
+              {
+                break;
+              }
+
+              self.path_index = self.wildcard.path_index as usize;
+              self.wildcard.path_index = self.path_index as u32 + 1;
+              if !program.dot && program.is_leading_dot(path, self.path_index) {
+                self.wildcard.path_index = path.len() as u32 + 1;
+                self.globstar = self.wildcard;
+                break;
+              }
+
+              self.skip_to_separator(program, path, !end);
+            }
+            continue;
+          }
+          Token::NoDot if !program.is_leading_dot(path, self.path_index) => {
+            self.glob_index += 1;
+            continue;
+          }
+          Token::Any
+            if self.path_index < path.len()
+              && !program.is_literal_separator(path[self.path_index]) =>
+          {
+            self.capture::<CAPTURE>(program, Some(self.path_index), self.path_index + 1);
+            self.glob_index += 1;
+            self.path_index += 1;
+            continue;
+          }
+          Token::Class { negated, len } if self.path_index < path.len() => {
+            let c = path[self.path_index];
+            let ranges = &tokens[self.glob_index + 1..self.glob_index + 1 + len as usize];
+            let is_match = |c: u8| {
+              ranges.iter().any(
+                |range| matches!(*range, Token::Range(low, high) if low <= c as u32 && c as u32 <= high),
+              ) != negated
+            };
+
+            if is_match(c) {
+              if !self.is_stretchable(program, is_match) {
+                self.capture::<CAPTURE>(program, Some(self.path_index), self.path_index + 1);
+                self.glob_index += 1 + len as usize;
+                self.path_index += 1;
+                continue;
+              }
+
+              let glob_index = self.glob_index + 1 + len as usize;
+              let end = self.path_index + 1;
+              if self.match_ends::<CAPTURE>(program, path, &[end], glob_index, failures) {
+                return true;
+              }
+            }
+          }
+          Token::AnyChar
+            if self.path_index < path.len()
+              && !program.is_literal_separator(path[self.path_index]) =>
+          {
+            let (_, len) = decode_char_lossy(&path[self.path_index..]);
+            self.capture::<CAPTURE>(program, Some(self.path_index), self.path_index + len);
+            self.glob_index += 1;
+            self.path_index += len;
+            continue;
+          }
+          Token::CharClass {
+            negated,
+            caseless,
+            len: ranges_len,
+          } if self.path_index < path.len() => {
+            let (c, len) = decode_char_lossy(&path[self.path_index..]);
+            let ranges = &tokens[self.glob_index + 1..self.glob_index + 1 + ranges_len as usize];
+
+            let is_match = |c: char| match_ranges(ranges, c, caseless) != negated;
+            if is_match(c) {
+              if !self.is_stretchable(program, |c| is_match(c as char)) {
+                self.capture::<CAPTURE>(program, Some(self.path_index), self.path_index + len);
+                self.glob_index += 1 + ranges_len as usize;
+                self.path_index += len;
+                continue;
+              }
+
+              let glob_index = self.glob_index + 1 + ranges_len as usize;
+              let end = self.path_index + len;
+              if self.match_ends::<CAPTURE>(program, path, &[end], glob_index, failures) {
+                return true;
+              }
+            }
+          }
+          Token::Open { next }
+            if self.match_brace::<CAPTURE>(program, path, next as usize, failures) =>
+          {
+            return true;
+          }
+          Token::Extglob { .. } if self.match_extglob::<CAPTURE>(program, path, failures) => {
+            return true;
+          }
+          Token::Sequence(index) => {
+            let ends = sequence_ends(&program.sequences[index as usize], path, self.path_index);
+            let glob_index = self.glob_index + 1;
+            if self.match_ends::<CAPTURE>(program, path, &ends, glob_index, failures) {
+              return true;
+            }
+          }
+          Token::Branch { end, .. } => {
+            self.capture::<CAPTURE>(program, None, self.path_index);
+            self.glob_index = end as usize;
+            continue;
+          }
+          Token::Close => {
+            self.capture::<CAPTURE>(program, None, self.path_index);
+            self.glob_index += 1;
+            continue;
+          }
+          Token::Separator | Token::Byte(_) if self.match_literals(program, path) => continue,
+          Token::Caseless(c)
+            if self.path_index < path.len() && path[self.path_index].to_ascii_lowercase() == c =>
+          {
+            self.glob_index += 1;
+            self.path_index += 1;
+            continue;
+          }
+          Token::CaselessChar(c) => {
+            if let Some((d, len)) = decode_char(&path[self.path_index..]) {
+              if fold_case(d) == c {
+                self.glob_index += 1;
+                self.path_index += len;
+                continue;
+              }
+            }
+          }
+          Token::Invalid => return false,
+          _ => {}
+        }
+      }
+
+      if self.wildcard.path_index > 0 && self.wildcard.path_index <= path.len() as u32 {
+        self.backtrack();
+        continue;
+      }
+
+      return false;
+    }
+  }
+}
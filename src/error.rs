@@ -0,0 +1,98 @@
+use std::{error::Error, fmt, ops::Range};
+
+/// An error describing why a glob pattern failed to compile.
+///
+/// The error points at the offending bytes of the pattern with [`GlobError::offset`] and
+/// [`GlobError::len`], which is enough to render a caret diagnostic:
+///
+/// ```rust
+/// use fast_glob::{ErrorKind, Glob};
+///
+/// let err = Glob::new("src/[a-z.rs").unwrap_err();
+///
+/// assert_eq!(err.kind(), ErrorKind::UnclosedClass);
+/// assert_eq!(err.span(), 4..5);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GlobError {
+  kind: ErrorKind,
+  offset: usize,
+  len: usize,
+}
+
+/// The kind of a [`GlobError`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+  /// A `[` without a matching `]`.
+  UnclosedClass,
+  /// A `{` without a matching `}`.
+  UnclosedBrace,
+  /// An extglob group such as `@(` without a matching `)`.
+  UnclosedGroup,
+  /// A `\` at the end of the pattern, with nothing to escape.
+  DanglingEscape,
+  /// A pattern entering more braces and extglob groups at once than
+  /// [`MatchOptions::max_brace_depth`] allows.
+  ///
+  /// [`MatchOptions::max_brace_depth`]: crate::MatchOptions::max_brace_depth
+  NestingTooDeep,
+  /// A character range such as `z-a` whose start is greater than its end.
+  EmptyRange,
+  /// A POSIX class such as `[:alpah:]` whose name isn't known.
+  UnknownClass,
+  /// A construct that [`to_regex`] can't translate, such as a `!(...)` extglob group.
+  ///
+  /// [`to_regex`]: crate::to_regex
+  Unsupported,
+}
+
+impl GlobError {
+  pub(crate) fn new(kind: ErrorKind, offset: usize, len: usize) -> GlobError {
+    GlobError { kind, offset, len }
+  }
+
+  /// Returns the kind of this error.
+  pub fn kind(&self) -> ErrorKind {
+    self.kind
+  }
+
+  /// Returns the byte offset in the pattern where the error starts.
+  pub fn offset(&self) -> usize {
+    self.offset
+  }
+
+  /// Returns the length in bytes of the part of the pattern the error refers to.
+  #[allow(clippy::len_without_is_empty)]
+  pub fn len(&self) -> usize {
+    self.len
+  }
+
+  /// Returns the byte range in the pattern the error refers to.
+  pub fn span(&self) -> Range<usize> {
+    self.offset..self.offset + self.len
+  }
+}
+
+impl fmt::Display for ErrorKind {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    f.write_str(match self {
+      ErrorKind::UnclosedClass => "unclosed character class",
+      ErrorKind::UnclosedBrace => "unclosed brace",
+      ErrorKind::UnclosedGroup => "unclosed extglob group",
+      ErrorKind::DanglingEscape => "dangling escape",
+      ErrorKind::NestingTooDeep => "braces or groups nested too deeply",
+      ErrorKind::EmptyRange => "empty character range",
+      ErrorKind::UnknownClass => "unknown character class",
+      ErrorKind::Unsupported => "construct not supported in a regular expression",
+    })
+  }
+}
+
+impl fmt::Display for GlobError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(f, "{} at byte {}", self.kind, self.offset)
+  }
+}
+
+impl Error for GlobError {}
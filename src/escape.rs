@@ -0,0 +1,65 @@
+use crate::Separator;
+
+/// The characters with a special meaning in patterns, which are escaped with a `\`.
+const SPECIAL: &[u8] = b"*?[]{}()!,\\";
+
+/// Escapes the characters of `text` that have a special meaning in patterns, so that the
+/// result matches `text` literally. A `/` is kept as it is, so it still matches any path
+/// separator and the result can be combined with other patterns.
+///
+/// Only punctuation is escaped, never a letter such as the `n` of `\n`. The result relies
+/// on [`MatchOptions::backslash_escape`](crate::MatchOptions::backslash_escape), which is
+/// set by default.
+///
+/// ```rust
+/// use fast_glob::{escape, glob_match};
+///
+/// let pattern = escape("report[2024]{final}.txt");
+///
+/// assert_eq!(pattern, r"report\[2024\]\{final\}.txt");
+/// assert!(glob_match(&pattern, "report[2024]{final}.txt"));
+/// assert!(!glob_match(&pattern, "report2.txt"));
+/// assert!(glob_match(format!("{}/**/*.rs", escape("src [old]")), "src [old]/a/b.rs"));
+/// ```
+pub fn escape(text: &str) -> String {
+  let mut pattern = String::with_capacity(text.len());
+  for c in text.chars() {
+    if c.is_ascii() && SPECIAL.contains(&(c as u8)) {
+      pattern.push('\\');
+    }
+    pattern.push(c);
+  }
+
+  pattern
+}
+
+/// Escapes `path` into a pattern that [`glob_match`](crate::glob_match) matches against
+/// `path` and nothing else.
+///
+/// Unlike [`escape`], `path` can be any bytes, and on hosts where `\` is a path separator
+/// too, a `/` is written as `[/]` so that it doesn't match a `\`.
+///
+/// ```rust
+/// use fast_glob::{escape_path, glob_match};
+///
+/// let pattern = escape_path("logs/*.log");
+///
+/// assert!(glob_match(&pattern, "logs/*.log"));
+/// assert!(!glob_match(&pattern, "logs/app.log"));
+/// assert!(!glob_match(&pattern, "logs\\*.log"));
+/// ```
+pub fn escape_path(path: impl AsRef<[u8]>) -> Vec<u8> {
+  let path = path.as_ref();
+  let is_slash_ambiguous = Separator::default() != Separator::Slash;
+
+  let mut pattern = Vec::with_capacity(path.len());
+  for &c in path {
+    match c {
+      b'/' if is_slash_ambiguous => pattern.extend_from_slice(b"[/]"),
+      c if SPECIAL.contains(&c) => pattern.extend_from_slice(&[b'\\', c]),
+      c => pattern.push(c),
+    }
+  }
+
+  pattern
+}
@@ -0,0 +1,190 @@
+use std::iter;
+
+use crate::{glob::parse_sequence, MatchOptions};
+
+/// The number of patterns [`expand_braces`] produces at most.
+pub const DEFAULT_EXPANSION_LIMIT: usize = 10_000;
+
+/// Expands the braces of `pattern` into the patterns they stand for, in order.
+///
+/// Both alternatives like `{a,b}` and sequences like `{1..3}` are expanded, including
+/// nested ones. Commas and braces inside `[...]` or escaped with `\` are left as they are,
+/// as is a `{` without a matching `}`. Every other part of the pattern is copied verbatim,
+/// so the expansions are still globs, except that a `!` ending up at the start of one is
+/// escaped so as not to negate it.
+///
+/// At most [`DEFAULT_EXPANSION_LIMIT`] patterns are produced; use
+/// [`expand_braces_with_limit`] to choose another cap.
+///
+/// ```rust
+/// use fast_glob::expand_braces;
+///
+/// let globs: Vec<String> = expand_braces("{src,test}/**/*.{ts,tsx}").collect();
+///
+/// assert_eq!(
+///   globs,
+///   ["src/**/*.ts", "src/**/*.tsx", "test/**/*.ts", "test/**/*.tsx"]
+/// );
+/// ```
+pub fn expand_braces(pattern: &str) -> impl Iterator<Item = String> {
+  expand_braces_with_limit(pattern, DEFAULT_EXPANSION_LIMIT)
+}
+
+/// Expands the braces of `pattern` like [`expand_braces`], producing at most `limit`
+/// patterns. Expansion stops as soon as the limit is reached, so a pattern with a huge
+/// number of expansions costs no more than its first `limit`.
+///
+/// ```rust
+/// use fast_glob::expand_braces_with_limit;
+///
+/// let files: Vec<String> = expand_braces_with_limit("{a,b}/{1..1000000}.json", 3).collect();
+///
+/// assert_eq!(files, ["a/1.json", "a/2.json", "a/3.json"]);
+/// ```
+pub fn expand_braces_with_limit(pattern: &str, limit: usize) -> impl Iterator<Item = String> {
+  // Only the `!`s the pattern starts with negate it. Any other `!` ending up at the start
+  // of an expansion, when what came before it expanded to nothing, is escaped.
+  let negations = pattern.len() - pattern.trim_start_matches('!').len();
+  let (expansions, _) = expand(pattern, negations, false, limit);
+  expansions.into_iter().take(limit).map(move |expansion| {
+    let escape = if expansion.starts_with('!') { "\\" } else { "" };
+    format!("{}{escape}{expansion}", "!".repeat(negations))
+  })
+}
+
+/// Expands `pattern[index..]` up to its end or, if `in_brace` is set, up to the `,` or `}`
+/// ending the current alternative. Returns the expansions along with the index where it
+/// stopped.
+fn expand(pattern: &str, mut index: usize, in_brace: bool, limit: usize) -> (Vec<String>, usize) {
+  let bytes = pattern.as_bytes();
+  let mut expansions = vec![String::new()];
+  let mut literal_start = index;
+
+  while index < bytes.len() {
+    match bytes[index] {
+      b'\\' => index += escape_len(pattern, index),
+      b'[' => index = skip_class(pattern, index),
+      b',' | b'}' if in_brace => break,
+      b'{' => {
+        let Some((alternatives, end)) = expand_brace(pattern, index, limit) else {
+          index += 1;
+          continue;
+        };
+
+        let literal = &pattern[literal_start..index];
+        let mut product = Vec::new();
+        'product: for prefix in &expansions {
+          for alternative in &alternatives {
+            if product.len() == limit {
+              break 'product;
+            }
+            product.push([prefix, literal, alternative].concat());
+          }
+        }
+
+        expansions = product;
+        index = end;
+        literal_start = index;
+      }
+      _ => index += 1,
+    }
+  }
+
+  let literal = &pattern[literal_start..index];
+  for expansion in &mut expansions {
+    expansion.push_str(literal);
+  }
+
+  (expansions, index)
+}
+
+/// Expands the brace at `index`, returning its alternatives and the index just past it, or
+/// `None` if the brace is never closed.
+fn expand_brace(pattern: &str, index: usize, limit: usize) -> Option<(Vec<String>, usize)> {
+  let bytes = pattern.as_bytes();
+
+  if let Some((sequence, end)) = parse_sequence(bytes, index, &MatchOptions::default()) {
+    let step = if sequence.start <= sequence.end {
+      sequence.step
+    } else {
+      -sequence.step
+    };
+
+    // The values stop short of overflowing at either end of the `i64` range.
+    let values = iter::successors(Some(sequence.start), |value| value.checked_add(step));
+    let values = values.take_while(|&value| sequence.contains(value));
+    let values = values.take(limit).map(|value| {
+      if !sequence.chars {
+        return format!("{:0width$}", value, width = sequence.width);
+      }
+
+      match value as u8 {
+        c @ (b'[' | b']' | b'\\') => format!("\\{}", c as char),
+        c => (c as char).to_string(),
+      }
+    });
+    return Some((values.collect(), end));
+  }
+
+  let mut alternatives = Vec::new();
+  let mut index = index + 1;
+  loop {
+    let (expansions, end) = expand(pattern, index, true, limit);
+    let remaining = limit - alternatives.len();
+    alternatives.extend(expansions.into_iter().take(remaining));
+
+    match bytes.get(end)? {
+      b',' => index = end + 1,
+      _ => return Some((alternatives, end + 1)),
+    }
+  }
+}
+
+/// Returns the index just past the class starting with the `[` at `index`, or past the `[`
+/// alone if the class is never closed.
+fn skip_class(pattern: &str, index: usize) -> usize {
+  let bytes = pattern.as_bytes();
+  let mut end = index + 1;
+  if matches!(bytes.get(end), Some(b'!' | b'^')) {
+    end += 1;
+  }
+
+  let mut first = true;
+  while end < bytes.len() && (first || bytes[end] != b']') {
+    end += match bytes[end] {
+      b'\\' => escape_len(pattern, end),
+      b'[' if bytes.get(end + 1) == Some(&b':') => posix_class_len(&bytes[end..]),
+      _ => 1,
+    };
+    first = false;
+  }
+
+  if end < bytes.len() {
+    end + 1
+  } else {
+    index + 1
+  }
+}
+
+/// Returns the length of the escape sequence starting with the `\\` at `index`.
+fn escape_len(pattern: &str, index: usize) -> usize {
+  1 + pattern[index + 1..]
+    .chars()
+    .next()
+    .map_or(0, char::len_utf8)
+}
+
+/// Returns the length of the POSIX class like `[:alpha:]` at the start of `bytes`, or 1 if
+/// there is none and the `[` is a literal.
+fn posix_class_len(bytes: &[u8]) -> usize {
+  let name_len = bytes[2..]
+    .iter()
+    .take_while(|c| c.is_ascii_alphabetic())
+    .count();
+
+  if bytes[2 + name_len..].starts_with(b":]") {
+    name_len + 4
+  } else {
+    1
+  }
+}
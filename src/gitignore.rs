@@ -0,0 +1,192 @@
+//! Matching paths against the lines of a `.gitignore` file.
+//!
+//! ```rust
+//! use fast_glob::gitignore::{Gitignore, Match};
+//!
+//! let gitignore = Gitignore::new(["target/", "*.log", "!important.log", "/build"]);
+//!
+//! assert_eq!(gitignore.matched("target", true), Match::Ignore);
+//! assert_eq!(gitignore.matched("logs/debug.log", false), Match::Ignore);
+//! assert_eq!(gitignore.matched("logs/important.log", false), Match::Whitelist);
+//! assert_eq!(gitignore.matched("src/build", false), Match::None);
+//! ```
+
+use crate::{Glob, Separator};
+
+/// A compiled list of gitignore patterns.
+///
+/// Patterns follow the rules of [gitignore](https://git-scm.com/docs/gitignore):
+///
+/// - Blank lines and lines starting with `#` are skipped, and trailing spaces are removed
+///   unless escaped with `\`.
+/// - A pattern without a `/` other than a trailing one matches at any depth, while any
+///   other pattern is relative to the directory of the ignore file. A leading `/` only
+///   anchors the pattern.
+/// - A trailing `/` makes the pattern match directories only.
+/// - A leading `!` makes the pattern re-include what an earlier pattern excluded.
+/// - `*` and `?` never match a `/`, and `**` only matches whole path segments,
+///   as in `**/foo`, `foo/**` and `a/**/b`. Braces have no special meaning.
+///
+/// When several patterns match a path, the last one wins. A pattern that can't be
+/// compiled, such as one with an unclosed `[` or ending in a lone `\`, never matches, as
+/// with git.
+#[derive(Clone, Debug)]
+pub struct Gitignore {
+  rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+  glob: Glob,
+  whitelist: bool,
+  dir_only: bool,
+}
+
+/// The outcome of matching a path against a [`Gitignore`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Match {
+  /// No pattern matches the path.
+  None,
+  /// The last pattern matching the path excludes it.
+  Ignore,
+  /// The last pattern matching the path is a `!` pattern, re-including it.
+  Whitelist,
+}
+
+impl Gitignore {
+  /// Compiles the gitignore `lines`, such as the [`lines`](str::lines) of a `.gitignore`
+  /// file. Paths matched later are relative to the directory of that file.
+  pub fn new<I>(lines: I) -> Gitignore
+  where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+  {
+    let rules = lines
+      .into_iter()
+      .filter_map(|line| Rule::parse(line.as_ref()))
+      .collect();
+
+    Gitignore { rules }
+  }
+
+  /// Returns the number of patterns, not counting blank lines and comments.
+  pub fn len(&self) -> usize {
+    self.rules.len()
+  }
+
+  /// Returns whether there are no patterns.
+  pub fn is_empty(&self) -> bool {
+    self.rules.is_empty()
+  }
+
+  /// Matches `path`, relative to the directory of the ignore file, where `is_dir` tells
+  /// whether it is a directory.
+  ///
+  /// Only the path itself is matched, so `foo/bar` isn't ignored by `foo/` although git
+  /// never looks inside an ignored directory. Use
+  /// [`Gitignore::matched_path_or_any_parents`] when the parents aren't matched already.
+  pub fn matched(&self, path: impl AsRef<[u8]>, is_dir: bool) -> Match {
+    let path = path.as_ref();
+
+    let rule = self
+      .rules
+      .iter()
+      .rev()
+      .find(|rule| (is_dir || !rule.dir_only) && rule.glob.is_match(path));
+
+    match rule {
+      Some(rule) if rule.whitelist => Match::Whitelist,
+      Some(_) => Match::Ignore,
+      None => Match::None,
+    }
+  }
+
+  /// Matches `path` like [`Gitignore::matched`], except that it is ignored if any of its
+  /// parent directories is, since git can't re-include a file inside an ignored
+  /// directory.
+  ///
+  /// ```rust
+  /// use fast_glob::gitignore::{Gitignore, Match};
+  ///
+  /// let gitignore = Gitignore::new(["target/", "!*.rs"]);
+  ///
+  /// assert_eq!(gitignore.matched("target/main.rs", false), Match::Whitelist);
+  /// assert_eq!(gitignore.matched_path_or_any_parents("target/main.rs", false), Match::Ignore);
+  /// ```
+  pub fn matched_path_or_any_parents(&self, path: impl AsRef<[u8]>, is_dir: bool) -> Match {
+    let path = path.as_ref();
+    let separators = Separator::default().bytes();
+
+    for (index, c) in path.iter().enumerate() {
+      if separators.contains(c) && index > 0 && self.matched(&path[..index], true) == Match::Ignore
+      {
+        return Match::Ignore;
+      }
+    }
+
+    self.matched(path, is_dir)
+  }
+}
+
+impl Rule {
+  /// Parses a gitignore line into the glob it stands for, or returns `None` for blank
+  /// lines and comments.
+  fn parse(line: &str) -> Option<Rule> {
+    if line.starts_with('#') {
+      return None;
+    }
+
+    let (whitelist, mut line) = match line.strip_prefix('!') {
+      Some(line) => (true, line),
+      None => (false, line),
+    };
+
+    // Trailing spaces are removed, unless the last of them is escaped.
+    while line.ends_with(' ') && !line[..line.len() - 1].ends_with('\\') {
+      line = &line[..line.len() - 1];
+    }
+
+    let (dir_only, line) = match line.strip_suffix('/') {
+      Some(line) => (true, line),
+      None => (false, line),
+    };
+
+    let anchored = line.contains('/');
+    let line = line.strip_prefix('/').unwrap_or(line);
+    if line.is_empty() {
+      return None;
+    }
+
+    let mut glob = String::with_capacity(line.len() + 3);
+    if !anchored {
+      glob.push_str("**/");
+    } else if line.starts_with('!') {
+      // The pattern would otherwise be negated.
+      glob.push('\\');
+    }
+
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+      match c {
+        // Git has no escape sequences, so `\n` is a plain `n`.
+        '\\' => match chars.next() {
+          Some(c @ ('\\' | '*' | '?' | '[' | ']' | '{' | '!' | ' ')) => {
+            glob.push('\\');
+            glob.push(c);
+          }
+          Some(c) => glob.push(c),
+          // A dangling escape makes the pattern invalid, so it never matches.
+          None => glob.push('\\'),
+        },
+        '{' => glob.push_str("\\{"),
+        c => glob.push(c),
+      }
+    }
+
+    Some(Rule {
+      glob: Glob::new_lossy(glob.as_bytes()),
+      whitelist,
+      dir_only,
+    })
+  }
+}
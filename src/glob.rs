@@ -0,0 +1,1154 @@
+use std::ops::{DerefMut, Range};
+
+use arrayvec::ArrayVec;
+
+use crate::{
+  engine::{self, decode_char, decode_char_lossy, fold_case, NO_CAPTURE},
+  ErrorKind, GlobError, MatchOptions, Separator,
+};
+
+/// A glob pattern compiled once and matched against many paths.
+///
+/// `Glob` does the lexing work of [`glob_match`](crate::glob_match) up front: braces,
+/// brackets, escapes and runs of globstars are resolved into tokens when the pattern is
+/// compiled, so [`Glob::is_match`] only has to walk the path.
+///
+/// ```rust
+/// use fast_glob::Glob;
+///
+/// let glob = Glob::new("some/**/{tob,crazy}/?*.{png,txt}").unwrap();
+///
+/// assert!(glob.is_match("some/a/bigger/path/to/the/crazy/needle.txt"));
+/// assert!(!glob.is_match("some/a/bigger/path/to/the/crazy/needle.jpg"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Glob {
+  pub(crate) tokens: Vec<Token>,
+  /// The sequence braces referred to by [`Token::Sequence`].
+  pub(crate) sequences: Vec<Sequence>,
+  pub(crate) negated: bool,
+  /// Whether the pattern is matched against the last segment of paths, see
+  /// [`MatchOptions::basename`].
+  pub(crate) basename: bool,
+  pub(crate) options: MatchOptions,
+}
+
+/// The part of a path matched by a wildcard, character class or brace in a glob.
+///
+/// See [`Glob::captures`].
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Capture {
+  pub(crate) range: Range<usize>,
+  pub(crate) branch: Option<usize>,
+}
+
+impl Capture {
+  /// Returns the byte range of the path matched by this capture.
+  pub fn range(&self) -> Range<usize> {
+    self.range.clone()
+  }
+
+  /// Returns the index of the alternative taken, if this capture is a brace.
+  pub fn branch(&self) -> Option<usize> {
+    self.branch
+  }
+}
+
+/// Whether the paths inside a directory can match a glob.
+///
+/// See [`Glob::could_match_descendant`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DescendantMatch {
+  /// No path inside the directory matches, so it doesn't need to be visited.
+  No,
+  /// Some paths inside the directory may match.
+  Maybe,
+  /// Every path inside the directory matches.
+  Yes,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Token {
+  /// A literal byte, with escapes already resolved.
+  Byte(u8),
+  /// An ASCII letter matched regardless of case, stored in lowercase.
+  Caseless(u8),
+  /// A non-ASCII character matched regardless of case, stored case-folded.
+  CaselessChar(char),
+  /// A literal `/`, which matches any path separator.
+  Separator,
+  /// `?`
+  Any,
+  /// `?` in UTF-8 mode, matching a whole character.
+  AnyChar,
+  /// `*`, or a `**` that doesn't span whole path segments.
+  Star,
+  /// Placed ahead of a wildcard that can't match the `.` starting a path segment, fails
+  /// if the path is at such a dot.
+  NoDot,
+  /// `**` spanning whole path segments, including the `/` following it unless `end` is set.
+  Globstar { end: bool },
+  /// `[...]`, followed by `len` [`Token::Range`] tokens.
+  Class { negated: bool, len: u32 },
+  /// `[...]` in UTF-8 mode, followed by `len` [`Token::Range`] tokens of code points. A
+  /// `caseless` class also matches the case mappings of non-ASCII characters.
+  CharClass {
+    negated: bool,
+    caseless: bool,
+    len: u32,
+  },
+  /// An inclusive range of bytes, or of code points in a [`Token::CharClass`].
+  Range(u32, u32),
+  /// `{`, where `next` is the index of the first [`Token::Branch`] or [`Token::Close`].
+  Open { next: u32 },
+  /// An extglob group like `@(`, where `next` is the index of the first [`Token::Branch`]
+  /// or [`Token::Close`] and `end` is the index right after the group. The group can only
+  /// match across path separators if `multi_segment` is set.
+  Extglob {
+    kind: ExtglobKind,
+    multi_segment: bool,
+    next: u32,
+    end: u32,
+  },
+  /// A sequence brace like `{1..10}`, indexing into [`Glob::sequences`].
+  Sequence(u32),
+  /// `,` inside braces or `|` inside an extglob group, where `next` is the index of the
+  /// following [`Token::Branch`] or [`Token::Close`] and `end` is the index right after the
+  /// closing brace.
+  Branch { next: u32, end: u32 },
+  /// `}` closing a brace, or `)` closing an extglob group.
+  Close,
+  /// A malformed pattern, which never matches.
+  Invalid,
+}
+
+/// A sequence brace such as `{01..20..2}` or `{a..e}`, matching one of the values from
+/// `start` to `end` counting by `step`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct Sequence {
+  pub(crate) start: i64,
+  pub(crate) end: i64,
+  pub(crate) step: i64,
+  /// The width numbers are zero-padded to, or 0 if they aren't padded.
+  pub(crate) width: usize,
+  /// Whether the values are ASCII characters rather than numbers.
+  pub(crate) chars: bool,
+  pub(crate) caseless: bool,
+}
+
+impl Sequence {
+  /// Returns whether `value` is one of the values of this sequence.
+  pub(crate) fn contains(&self, value: i64) -> bool {
+    // The distance between two values can exceed `i64::MAX`.
+    value >= self.start.min(self.end)
+      && value <= self.start.max(self.end)
+      && (value as i128 - self.start as i128) % self.step as i128 == 0
+  }
+}
+
+/// The operator of an extglob group, which determines how many times its alternatives
+/// may match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ExtglobKind {
+  /// `@(...)`, exactly once.
+  One,
+  /// `?(...)`, zero or one time.
+  ZeroOrOne,
+  /// `*(...)`, any number of times.
+  ZeroOrMore,
+  /// `+(...)`, at least once.
+  OneOrMore,
+  /// `!(...)`, anything but one of the alternatives.
+  Not,
+}
+
+/// The inclusive byte ranges making up a POSIX class.
+type PosixRanges = &'static [(u8, u8)];
+
+/// The ASCII ranges of each POSIX class usable inside brackets, such as `[[:alpha:]]`.
+const POSIX_CLASSES: &[(&[u8], PosixRanges)] = &[
+  (b"alnum", &[(b'0', b'9'), (b'A', b'Z'), (b'a', b'z')]),
+  (b"alpha", &[(b'A', b'Z'), (b'a', b'z')]),
+  (b"ascii", &[(0x00, 0x7f)]),
+  (b"blank", &[(b' ', b' '), (b'\t', b'\t')]),
+  (b"cntrl", &[(0x00, 0x1f), (0x7f, 0x7f)]),
+  (b"digit", &[(b'0', b'9')]),
+  (b"graph", &[(b'!', b'~')]),
+  (b"lower", &[(b'a', b'z')]),
+  (b"print", &[(b' ', b'~')]),
+  (
+    b"punct",
+    &[(b'!', b'/'), (b':', b'@'), (b'[', b'`'), (b'{', b'~')],
+  ),
+  (b"space", &[(b'\t', b'\r'), (b' ', b' ')]),
+  (b"upper", &[(b'A', b'Z')]),
+  (
+    b"word",
+    &[(b'0', b'9'), (b'A', b'Z'), (b'_', b'_'), (b'a', b'z')],
+  ),
+  (b"xdigit", &[(b'0', b'9'), (b'A', b'F'), (b'a', b'f')]),
+];
+
+impl Glob {
+  /// Compiles `glob` into a reusable matcher.
+  ///
+  /// Returns a [`GlobError`] pointing at the problem if the pattern is malformed, such as
+  /// an unterminated `[` or `{`.
+  pub fn new(glob: impl AsRef<[u8]>) -> Result<Glob, GlobError> {
+    Glob::compile(glob.as_ref(), &MatchOptions::default(), true)
+  }
+
+  /// Compiles `glob` into a reusable matcher with the given options.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions};
+  ///
+  /// let options = MatchOptions::new().case_insensitive(true);
+  /// let glob = Glob::with_options("src/[a-c]*.{RS,toml}", options).unwrap();
+  ///
+  /// assert!(glob.is_match("SRC/Build.rs"));
+  /// assert!(glob.is_match("src/cargo.TOML"));
+  /// ```
+  pub fn with_options(glob: impl AsRef<[u8]>, options: MatchOptions) -> Result<Glob, GlobError> {
+    Glob::compile(glob.as_ref(), &options, true)
+  }
+
+  /// Compiles `glob` the way [`glob_match`](crate::glob_match) interprets it: a malformed
+  /// pattern never matches, but can still be negated.
+  pub(crate) fn new_lossy(glob: &[u8]) -> Glob {
+    Glob::compile(glob, &MatchOptions::default(), false).unwrap_or_else(|_| Glob {
+      tokens: vec![Token::Invalid],
+      sequences: Vec::new(),
+      negated: negation(glob, &MatchOptions::default()).0,
+      basename: false,
+      options: MatchOptions::default(),
+    })
+  }
+
+  fn compile(glob: &[u8], options: &MatchOptions, strict: bool) -> Result<Glob, GlobError> {
+    let (negated, glob_index) = negation(glob, options);
+
+    let mut tokens = Vec::with_capacity(glob.len() - glob_index);
+    let mut sequences = Vec::new();
+    parse(
+      glob,
+      glob_index,
+      options,
+      strict,
+      &mut tokens,
+      &mut sequences,
+    )?;
+    // A separator can also be folded into a globstar, so the pattern itself is checked.
+    let basename =
+      options.basename && !glob[glob_index..].iter().any(|&c| is_separator(c, options));
+
+    Ok(Glob {
+      tokens,
+      sequences,
+      negated,
+      basename,
+      options: *options,
+    })
+  }
+
+  /// Returns whether this glob only matches the path spelled out by the pattern, once
+  /// escapes are resolved, so that checking the path exists is enough to find its match.
+  ///
+  /// ```rust
+  /// use fast_glob::Glob;
+  ///
+  /// assert!(Glob::new("src/lib.rs").unwrap().is_literal());
+  /// assert!(Glob::new("src/\\*.rs").unwrap().is_literal());
+  /// assert!(!Glob::new("src/{lib,main}.rs").unwrap().is_literal());
+  /// assert!(!Glob::new("!src/lib.rs").unwrap().is_literal());
+  /// ```
+  pub fn is_literal(&self) -> bool {
+    !self.negated
+      && !self.basename
+      && self
+        .tokens
+        .iter()
+        .all(|token| matches!(token, Token::Byte(_) | Token::Separator))
+  }
+
+  /// Returns whether this glob can match paths beyond a fixed list, which is the case
+  /// when it has a wildcard, a character class, an extglob group or a caseless letter, or
+  /// when it is negated or matched against basenames. Braces alone, including sequences
+  /// like `{1..3}`, only expand into a list of literal paths and aren't magic.
+  ///
+  /// ```rust
+  /// use fast_glob::Glob;
+  ///
+  /// assert!(!Glob::new("src/{lib,main}.rs").unwrap().has_magic());
+  /// assert!(!Glob::new("src/\\*.rs").unwrap().has_magic());
+  /// assert!(Glob::new("src/*.rs").unwrap().has_magic());
+  /// assert!(Glob::new("src/[lm]*.rs").unwrap().has_magic());
+  /// ```
+  pub fn has_magic(&self) -> bool {
+    self.negated
+      || self.basename
+      || self.tokens.iter().any(|token| {
+        !matches!(
+          token,
+          Token::Byte(_)
+            | Token::Separator
+            | Token::Open { .. }
+            | Token::Branch { .. }
+            | Token::Close
+            | Token::Sequence(_)
+        )
+      })
+  }
+
+  /// Returns the literal directory every match starts in, like `glob-parent`, with `/`
+  /// between segments and escapes resolved. It is empty if the first path segment of the
+  /// pattern isn't a literal, such as a wildcard or a brace, or if the glob is negated.
+  /// When this glob [is literal](Glob::is_literal), the base is the parent directory of
+  /// the path.
+  ///
+  /// ```rust
+  /// use fast_glob::Glob;
+  ///
+  /// assert_eq!(Glob::new("src/vs/**/test/*.ts").unwrap().static_base(), b"src/vs");
+  /// assert_eq!(Glob::new("src/{a,b}/c").unwrap().static_base(), b"src");
+  /// assert_eq!(Glob::new("{a,b}/c").unwrap().static_base(), b"");
+  /// assert_eq!(Glob::new("*.rs").unwrap().static_base(), b"");
+  /// ```
+  pub fn static_base(&self) -> Vec<u8> {
+    if self.negated {
+      return Vec::new();
+    }
+
+    let mut base = Vec::new();
+    let mut len = 0;
+    for token in &self.tokens {
+      match *token {
+        Token::Byte(c) => base.push(c),
+        Token::Separator => {
+          len = base.len();
+          base.push(b'/');
+        }
+        _ => break,
+      }
+    }
+
+    base.truncate(len);
+    base
+  }
+
+  /// Returns the index where the part of `path` matched by this glob starts, which is
+  /// after the last separator for a basename pattern.
+  pub(crate) fn match_start(&self, path: &[u8]) -> usize {
+    if !self.basename {
+      return 0;
+    }
+
+    let separators = self.options.separator.bytes();
+    path
+      .iter()
+      .rposition(|c| separators.contains(c))
+      .map_or(0, |index| index + 1)
+  }
+
+  /// Returns whether `path` matches this glob.
+  pub fn is_match(&self, path: impl AsRef<[u8]>) -> bool {
+    self.negated ^ engine::match_glob(self, path.as_ref())
+  }
+
+  /// Returns whether paths inside the directory `dir` can match this glob, so that a
+  /// directory walk can skip subtrees without any matches. The directory itself isn't
+  /// one of its descendants. An empty `dir` stands for the directory the glob is
+  /// relative to.
+  ///
+  /// The answer is exact for `No`: a directory reported as `No` never contains a match.
+  /// `Maybe` can be returned for a directory without matches, for example when only its
+  /// file names could rule them out.
+  ///
+  /// ```rust
+  /// use fast_glob::{DescendantMatch, Glob};
+  ///
+  /// let glob = Glob::new("{src,extensions}/**/test/**/*.ts").unwrap();
+  ///
+  /// assert_eq!(glob.could_match_descendant("src/vs/base"), DescendantMatch::Maybe);
+  /// assert_eq!(glob.could_match_descendant("docs"), DescendantMatch::No);
+  ///
+  /// let glob = Glob::new("src/**").unwrap();
+  ///
+  /// assert_eq!(glob.could_match_descendant("src/vs"), DescendantMatch::Yes);
+  /// ```
+  pub fn could_match_descendant(&self, dir: impl AsRef<[u8]>) -> DescendantMatch {
+    if self.basename {
+      // Any directory can contain a file whose name matches.
+      return DescendantMatch::Maybe;
+    }
+
+    let dir = dir.as_ref();
+    let separators = self.options.separator.bytes();
+
+    let result = match dir.last() {
+      Some(c) if !separators.contains(c) => {
+        let mut dir = dir.to_vec();
+        dir.push(separators[0]);
+        engine::match_descendants(self, &dir)
+      }
+      _ => engine::match_descendants(self, dir),
+    };
+
+    match (self.negated, result) {
+      (true, DescendantMatch::No) => DescendantMatch::Yes,
+      (true, DescendantMatch::Yes) => DescendantMatch::No,
+      _ => result,
+    }
+  }
+
+  /// Matches `path` and returns what each `*`, `**`, `?`, `[...]` and `{...}` of the
+  /// pattern matched, in pattern order, or `None` if the path doesn't match.
+  ///
+  /// A brace is captured as a whole, along with the index of the alternative taken, so
+  /// wildcards nested inside braces don't produce captures of their own. Extglob groups
+  /// are captured as a whole too, without an alternative index. The `/` following
+  /// a `**` isn't part of its capture. A negated glob has nothing to capture, so it returns
+  /// an empty list when it matches.
+  ///
+  /// ```rust
+  /// use fast_glob::Glob;
+  ///
+  /// let glob = Glob::new("src/**/*.{ts,js}").unwrap();
+  /// let path = "src/a/b/index.js";
+  /// let captures = glob.captures(path).unwrap();
+  ///
+  /// assert_eq!(&path[captures[0].range()], "a/b");
+  /// assert_eq!(&path[captures[1].range()], "index");
+  /// assert_eq!(&path[captures[2].range()], "js");
+  /// assert_eq!(captures[2].branch(), Some(1));
+  /// ```
+  pub fn captures(&self, path: impl AsRef<[u8]>) -> Option<Vec<Capture>> {
+    let path = path.as_ref();
+    if self.negated {
+      return (!engine::match_glob(self, path)).then(Vec::new);
+    }
+
+    let mut slots = vec![NO_CAPTURE; self.tokens.len()];
+    // The slot of each open brace, which its branch separators record into.
+    let mut braces = Vec::new();
+    let mut count = 0;
+    for (index, token) in self.tokens.iter().enumerate() {
+      match token {
+        Token::Star
+        | Token::Globstar { .. }
+        | Token::Any
+        | Token::AnyChar
+        | Token::Class { .. }
+        | Token::CharClass { .. }
+        | Token::Sequence(_)
+          if braces.is_empty() =>
+        {
+          slots[index] = count;
+          count += 1;
+        }
+        Token::Open { .. } | Token::Extglob { .. } => {
+          if braces.is_empty() {
+            slots[index] = count;
+            count += 1;
+          }
+          braces.push(slots[index]);
+        }
+        Token::Branch { .. } => slots[index] = *braces.last().unwrap(),
+        Token::Close => slots[index] = braces.pop().unwrap(),
+        _ => {}
+      }
+    }
+
+    let start = self.match_start(path);
+    let mut captures = engine::match_captures(self, &slots, count as usize, &path[start..])?;
+    for capture in &mut captures {
+      capture.range.start += start;
+      capture.range.end += start;
+    }
+
+    Some(captures)
+  }
+}
+
+/// Matches `path` against `glob` the way [`Glob::new_lossy`] compiles it, without
+/// allocating for patterns short enough to be parsed into an inline buffer.
+pub(crate) fn match_lossy(glob: &[u8], path: &[u8]) -> bool {
+  let options = MatchOptions::default();
+  let (negated, glob_index) = negation(glob, &options);
+
+  // Under the default options, no pattern byte is parsed into more than one token.
+  let matched = if glob.len() <= 64 {
+    let mut tokens = ArrayVec::<Token, 64>::new();
+    parse_and_match(glob, glob_index, &options, &mut tokens, path)
+  } else {
+    parse_and_match(glob, glob_index, &options, &mut Vec::new(), path)
+  };
+
+  negated ^ matched
+}
+
+/// Parses `glob[glob_index..]` into `tokens` and matches `path` against it. A malformed
+/// pattern never matches.
+#[inline(always)]
+fn parse_and_match(
+  glob: &[u8],
+  glob_index: usize,
+  options: &MatchOptions,
+  tokens: &mut impl Tokens,
+  path: &[u8],
+) -> bool {
+  let mut sequences = Vec::new();
+  parse(glob, glob_index, options, false, tokens, &mut sequences).is_ok()
+    && engine::match_tokens(tokens, &sequences, options, path)
+}
+
+/// Returns whether `glob` is negated by its leading `!` characters, along with the index
+/// of the pattern following them.
+fn negation(glob: &[u8], options: &MatchOptions) -> (bool, usize) {
+  let mut negated = false;
+  let mut glob_index = 0;
+  while glob_index < glob.len()
+    && glob[glob_index] == b'!'
+    && !(options.extglob && glob.get(glob_index + 1) == Some(&b'('))
+  {
+    negated = !negated;
+    glob_index += 1;
+  }
+
+  (negated, glob_index)
+}
+
+/// Where [`parse`] writes the tokens of a pattern.
+trait Tokens: DerefMut<Target = [Token]> {
+  fn push(&mut self, token: Token);
+}
+
+impl Tokens for Vec<Token> {
+  #[inline(always)]
+  fn push(&mut self, token: Token) {
+    Vec::push(self, token);
+  }
+}
+
+impl<const N: usize> Tokens for ArrayVec<Token, N> {
+  #[inline(always)]
+  fn push(&mut self, token: Token) {
+    ArrayVec::push(self, token);
+  }
+}
+
+#[inline(always)]
+fn unescape(c: u8) -> u8 {
+  match c {
+    b'a' => b'\x61',
+    b'b' => b'\x08',
+    b'n' => b'\n',
+    b'r' => b'\r',
+    b't' => b'\t',
+    c => c,
+  }
+}
+
+/// Returns whether the pattern byte `c` is always a literal matching itself when
+/// matching case-sensitively.
+#[inline(always)]
+fn is_plain(c: u8) -> bool {
+  c.is_ascii_alphanumeric() || matches!(c, b'.' | b'-' | b'_' | b' ')
+}
+
+/// Returns whether the pattern byte `c` is a path separator. A `\\` is one when it isn't an
+/// escape and separates paths.
+#[inline(always)]
+fn is_separator(c: u8, options: &MatchOptions) -> bool {
+  c == b'/' || (c == b'\\' && !options.backslash_escape && options.separator != Separator::Slash)
+}
+
+/// Returns the index of the character at `glob_index` once a backslash escape is skipped.
+#[inline(always)]
+fn escaped_index(glob: &[u8], glob_index: usize, options: &MatchOptions) -> usize {
+  glob_index + (options.backslash_escape && glob[glob_index] == b'\\') as usize
+}
+
+/// Reads the byte at `glob_index`, resolving a backslash escape.
+#[inline(always)]
+fn read_byte(glob: &[u8], glob_index: &mut usize, options: &MatchOptions) -> Result<u8, GlobError> {
+  let c = glob[*glob_index];
+  *glob_index += 1;
+  if c != b'\\' || !options.backslash_escape {
+    return Ok(c);
+  }
+
+  let Some(&c) = glob.get(*glob_index) else {
+    return Err(GlobError::new(
+      ErrorKind::DanglingEscape,
+      *glob_index - 1,
+      1,
+    ));
+  };
+  *glob_index += 1;
+  Ok(unescape(c))
+}
+
+/// Reads the UTF-8 character at `glob_index`, resolving a backslash escape. A byte that
+/// isn't valid UTF-8 is read as U+FFFD.
+#[inline(always)]
+fn read_char(
+  glob: &[u8],
+  glob_index: &mut usize,
+  options: &MatchOptions,
+) -> Result<char, GlobError> {
+  let start = escaped_index(glob, *glob_index, options);
+  match glob.get(start) {
+    Some(c) if !c.is_ascii() => {
+      let (c, len) = decode_char_lossy(&glob[start..]);
+      *glob_index = start + len;
+      Ok(c)
+    }
+    _ => read_byte(glob, glob_index, options).map(char::from),
+  }
+}
+
+/// Returns the index just past the last `**` of a run like `**/**/**`.
+#[inline(always)]
+fn skip_globstars(glob: &[u8], mut glob_index: usize, options: &MatchOptions) -> usize {
+  glob_index += 2;
+
+  let is_globstar =
+    |index: usize| is_separator(glob[index], options) && glob[index + 1..].starts_with(b"**");
+
+  while glob_index + 4 <= glob.len()
+    && is_globstar(glob_index)
+    && is_separator(glob[glob_index + 3], options)
+  {
+    glob_index += 3;
+  }
+
+  if glob_index + 3 == glob.len() && is_globstar(glob_index) {
+    glob_index += 3;
+  }
+
+  glob_index
+}
+
+/// Links the branch separators of the brace or extglob group opened at `open` and closed
+/// at `close`.
+fn close_brace(tokens: &mut [Token], open: usize, close: usize) {
+  let end = close as u32 + 1;
+
+  let mut index = match &mut tokens[open] {
+    Token::Open { next } => *next as usize,
+    Token::Extglob {
+      next,
+      end: group_end,
+      ..
+    } => {
+      *group_end = end;
+      *next as usize
+    }
+    _ => unreachable!(),
+  };
+  while let Token::Branch {
+    next,
+    end: branch_end,
+  } = &mut tokens[index]
+  {
+    *branch_end = end;
+    index = *next as usize;
+  }
+}
+
+/// Pushes the range `low..=high` of a class, along with the case counterparts of any ASCII
+/// letters in it when matching case-insensitively.
+fn push_range(tokens: &mut impl Tokens, low: u32, high: u32, options: &MatchOptions) {
+  tokens.push(Token::Range(low, high));
+
+  if options.case_insensitive {
+    let (upper_low, upper_high) = (low.max(b'A' as u32), high.min(b'Z' as u32));
+    if upper_low <= upper_high {
+      tokens.push(Token::Range(upper_low + 32, upper_high + 32));
+    }
+
+    let (lower_low, lower_high) = (low.max(b'a' as u32), high.min(b'z' as u32));
+    if lower_low <= lower_high {
+      tokens.push(Token::Range(lower_low - 32, lower_high - 32));
+    }
+  }
+}
+
+/// Parses a POSIX class like `[:alpha:]` at `glob_index`, returning its ranges and the
+/// index just past it, or `None` if there is no closing `:]` and the `[` is a literal.
+/// An unknown class name has no ranges unless `strict` is set.
+pub(crate) fn parse_posix_class(
+  glob: &[u8],
+  glob_index: usize,
+  strict: bool,
+) -> Option<Result<(PosixRanges, usize), GlobError>> {
+  let name_start = glob_index + 2;
+  let name_len = glob[name_start..]
+    .iter()
+    .take_while(|c| c.is_ascii_alphabetic())
+    .count();
+  if !glob[name_start + name_len..].starts_with(b":]") {
+    return None;
+  }
+
+  let name = &glob[name_start..name_start + name_len];
+  let end = name_start + name_len + 2;
+
+  match POSIX_CLASSES.iter().find(|(class, _)| *class == name) {
+    Some((_, ranges)) => Some(Ok((ranges, end))),
+    None if strict => Some(Err(GlobError::new(
+      ErrorKind::UnknownClass,
+      glob_index,
+      end - glob_index,
+    ))),
+    None => Some(Ok((&[], end))),
+  }
+}
+
+/// Parses a sequence brace like `{1..10}`, `{01..20..2}` or `{a..e}` at `glob_index`,
+/// returning it with the index just past it, or `None` if the brace isn't a sequence.
+#[inline(always)]
+pub(crate) fn parse_sequence(
+  glob: &[u8],
+  glob_index: usize,
+  options: &MatchOptions,
+) -> Option<(Sequence, usize)> {
+  // Sequences are spelled with nothing but signs, digits, letters and dots, which rules
+  // out most braces before their body is looked at.
+  let len = glob[glob_index + 1..]
+    .iter()
+    .position(|&c| !matches!(c, b'+' | b'-' | b'.') && !c.is_ascii_alphanumeric())?
+    + 1;
+  if glob[glob_index + len] != b'}' {
+    return None;
+  }
+
+  let body = std::str::from_utf8(&glob[glob_index + 1..glob_index + len]).ok()?;
+  let sequence = parse_sequence_body(body, options)?;
+  Some((sequence, glob_index + len + 1))
+}
+
+/// Parses the body of a sequence brace, between the braces.
+fn parse_sequence_body(body: &str, options: &MatchOptions) -> Option<Sequence> {
+  let mut parts = body.split("..");
+  let (start, end) = (parts.next()?, parts.next()?);
+  let step = match parts.next() {
+    Some(step) => step.parse::<i64>().ok()?.checked_abs()?.max(1),
+    None => 1,
+  };
+  if parts.next().is_some() {
+    return None;
+  }
+
+  let is_number = |part: &str| {
+    let digits = part.strip_prefix(['-', '+']).unwrap_or(part);
+    !digits.is_empty() && digits.bytes().all(|c| c.is_ascii_digit())
+  };
+  let is_char = |part: &str| part.len() == 1 && part.as_bytes()[0].is_ascii_alphabetic();
+
+  let sequence = if is_number(start) && is_number(end) {
+    let is_padded = |part: &str| {
+      let digits = part.strip_prefix(['-', '+']).unwrap_or(part);
+      digits.len() > 1 && digits.starts_with('0')
+    };
+
+    Sequence {
+      start: start.parse().ok()?,
+      end: end.parse().ok()?,
+      step,
+      width: if is_padded(start) || is_padded(end) {
+        start.len().max(end.len())
+      } else {
+        0
+      },
+      chars: false,
+      caseless: false,
+    }
+  } else if is_char(start) && is_char(end) {
+    Sequence {
+      start: start.as_bytes()[0] as i64,
+      end: end.as_bytes()[0] as i64,
+      step,
+      width: 0,
+      chars: true,
+      caseless: options.case_insensitive,
+    }
+  } else {
+    return None;
+  };
+
+  Some(sequence)
+}
+
+/// Pushes a [`Token::NoDot`] ahead of a wildcard if wildcards can't match a leading dot.
+#[inline(always)]
+fn push_no_dot(tokens: &mut impl Tokens, options: &MatchOptions) {
+  if !options.dot {
+    tokens.push(Token::NoDot);
+  }
+}
+
+/// Pushes the literal at `glob_index`, resolving a backslash escape.
+#[inline(always)]
+fn push_literal(
+  tokens: &mut impl Tokens,
+  glob: &[u8],
+  glob_index: &mut usize,
+  options: &MatchOptions,
+) -> Result<(), GlobError> {
+  if options.case_insensitive {
+    let start = escaped_index(glob, *glob_index, options);
+    if let Some((c, len)) = glob.get(start..).and_then(decode_char) {
+      if !c.is_ascii() && c.to_lowercase().ne(c.to_uppercase()) {
+        tokens.push(Token::CaselessChar(fold_case(c)));
+        *glob_index = start + len;
+        return Ok(());
+      }
+    }
+  }
+
+  match read_byte(glob, glob_index, options)? {
+    c if is_separator(c, options) => tokens.push(Token::Separator),
+    c if options.case_insensitive && c.is_ascii_alphabetic() => {
+      tokens.push(Token::Caseless(c.to_ascii_lowercase()))
+    }
+    c => tokens.push(Token::Byte(c)),
+  }
+
+  Ok(())
+}
+
+/// A brace or extglob group being parsed.
+struct Group {
+  /// The index of its opening token.
+  open: usize,
+  /// The index of its last branch separator so far.
+  last: usize,
+  /// Its offset in the pattern.
+  offset: usize,
+  /// The number of braces entered before it.
+  depth: usize,
+  /// The most braces entered at the end of any of its alternatives so far.
+  max_depth: usize,
+}
+
+impl Group {
+  fn new(open: usize, offset: usize, depth: usize) -> Group {
+    Group {
+      open,
+      last: open,
+      offset,
+      depth,
+      max_depth: 0,
+    }
+  }
+}
+
+/// The braces and extglob groups being parsed, innermost last. Few patterns nest many
+/// groups, so the outermost ones are stored inline.
+#[derive(Default)]
+struct Groups {
+  inline: ArrayVec<Group, 8>,
+  spilled: Vec<Group>,
+}
+
+impl Groups {
+  #[inline(always)]
+  fn push(&mut self, group: Group) {
+    if let Err(err) = self.inline.try_push(group) {
+      self.spilled.push(err.element());
+    }
+  }
+
+  #[inline(always)]
+  fn pop(&mut self) {
+    if self.spilled.pop().is_none() {
+      self.inline.pop();
+    }
+  }
+
+  #[inline(always)]
+  fn last(&self) -> Option<&Group> {
+    self.spilled.last().or(self.inline.last())
+  }
+
+  #[inline(always)]
+  fn last_mut(&mut self) -> Option<&mut Group> {
+    match self.spilled.last_mut() {
+      Some(group) => Some(group),
+      None => self.inline.last_mut(),
+    }
+  }
+}
+
+/// Parses `glob[glob_index..]` into `tokens` and `sequences`. Unless `strict` is set,
+/// constructs that are well-formed but can't be matched, such as an empty range, are
+/// accepted.
+fn parse(
+  glob: &[u8],
+  mut glob_index: usize,
+  options: &MatchOptions,
+  strict: bool,
+  tokens: &mut impl Tokens,
+  sequences: &mut Vec<Sequence>,
+) -> Result<(), GlobError> {
+  let mut braces = Groups::default();
+  // The number of braces the engine has entered when reaching the current token, which
+  // includes earlier braces that have been closed since.
+  let mut depth = 0;
+
+  while glob_index < glob.len() {
+    // Common file name characters and separators make up most of a typical pattern, and
+    // need none of the checks below.
+    if !options.case_insensitive {
+      match glob[glob_index] {
+        c if is_plain(c) => {
+          tokens.push(Token::Byte(c));
+          glob_index += 1;
+          continue;
+        }
+        b'/' => {
+          tokens.push(Token::Separator);
+          glob_index += 1;
+          continue;
+        }
+        _ => {}
+      }
+    }
+
+    match glob[glob_index] {
+      b'@' | b'?' | b'*' | b'+' | b'!'
+        if options.extglob && glob.get(glob_index + 1) == Some(&b'(') =>
+      {
+        let kind = match glob[glob_index] {
+          b'@' => ExtglobKind::One,
+          b'?' => ExtglobKind::ZeroOrOne,
+          b'*' => ExtglobKind::ZeroOrMore,
+          b'+' => ExtglobKind::OneOrMore,
+          _ => ExtglobKind::Not,
+        };
+
+        // Alternatives are matched on their own, but by recursing from wherever the group
+        // is reached, so the group counts as one more level of nesting.
+        if depth == options.max_brace_depth {
+          return Err(GlobError::new(ErrorKind::NestingTooDeep, glob_index, 2));
+        }
+
+        // The alternatives keep wildcards from matching a leading dot themselves, but
+        // `!(...)` can match what none of them do.
+        if kind == ExtglobKind::Not {
+          push_no_dot(tokens, options);
+        }
+        braces.push(Group::new(tokens.len(), glob_index, depth));
+        depth += 1;
+        tokens.push(Token::Extglob {
+          kind,
+          multi_segment: false,
+          next: 0,
+          end: 0,
+        });
+        glob_index += 2;
+      }
+      b'*' => {
+        if glob_index + 1 < glob.len() && glob[glob_index + 1] == b'*' {
+          let end = skip_globstars(glob, glob_index, options);
+
+          // In `**(a)`, the second star opens a group.
+          if options.extglob && glob.get(end) == Some(&b'(') {
+            push_no_dot(tokens, options);
+            tokens.push(Token::Star);
+            glob_index += 1;
+            continue;
+          }
+
+          // A run of globstars only spans whole segments if its first one starts a
+          // segment, otherwise that one is a star followed by a separator.
+          let is_segment_start = matches!(
+            tokens.last(),
+            None
+              | Some(
+                Token::Separator
+                  | Token::Open { .. }
+                  | Token::Extglob { .. }
+                  | Token::Branch { .. }
+              )
+          );
+
+          if is_segment_start && end == glob.len() {
+            tokens.push(Token::Globstar { end: true });
+            glob_index = end;
+          } else if is_segment_start && is_separator(glob[end], options) {
+            tokens.push(Token::Globstar { end: false });
+            glob_index = end + 1;
+          } else {
+            push_no_dot(tokens, options);
+            tokens.push(Token::Star);
+            glob_index += 2;
+          }
+        } else {
+          push_no_dot(tokens, options);
+          tokens.push(Token::Star);
+          glob_index += 1;
+        }
+      }
+      b'?' => {
+        push_no_dot(tokens, options);
+        tokens.push(if options.utf8 {
+          Token::AnyChar
+        } else {
+          Token::Any
+        });
+        glob_index += 1;
+      }
+      b'[' => {
+        let class_offset = glob_index;
+        glob_index += 1;
+
+        let mut negated = false;
+        if glob_index < glob.len() && matches!(glob[glob_index], b'^' | b'!') {
+          negated = true;
+          glob_index += 1;
+        }
+
+        let read = |glob_index: &mut usize| -> Result<u32, GlobError> {
+          if options.utf8 {
+            read_char(glob, glob_index, options).map(u32::from)
+          } else {
+            read_byte(glob, glob_index, options).map(u32::from)
+          }
+        };
+
+        push_no_dot(tokens, options);
+        let class_index = tokens.len();
+        tokens.push(Token::Invalid);
+
+        let mut first = true;
+        while glob_index < glob.len() && (first || glob[glob_index] != b']') {
+          if glob[glob_index..].starts_with(b"[:") {
+            if let Some(class) = parse_posix_class(glob, glob_index, strict) {
+              let (ranges, end) = class?;
+              for &(low, high) in ranges {
+                push_range(tokens, low as u32, high as u32, options);
+              }
+              glob_index = end;
+              first = false;
+              continue;
+            }
+          }
+
+          let range_offset = glob_index;
+          let low = read(&mut glob_index)?;
+
+          let high = if glob_index + 1 < glob.len()
+            && glob[glob_index] == b'-'
+            && glob[glob_index + 1] != b']'
+          {
+            glob_index += 1;
+            read(&mut glob_index)?
+          } else {
+            low
+          };
+
+          if strict && low > high {
+            let len = glob_index - range_offset;
+            return Err(GlobError::new(ErrorKind::EmptyRange, range_offset, len));
+          }
+
+          push_range(tokens, low, high, options);
+          first = false;
+        }
+
+        if glob_index >= glob.len() {
+          return Err(GlobError::new(ErrorKind::UnclosedClass, class_offset, 1));
+        }
+        glob_index += 1;
+
+        let len = (tokens.len() - class_index - 1) as u32;
+        tokens[class_index] = if options.utf8 {
+          Token::CharClass {
+            negated,
+            caseless: options.case_insensitive,
+            len,
+          }
+        } else {
+          Token::Class { negated, len }
+        };
+      }
+      b'{' => {
+        if let Some((sequence, end)) = parse_sequence(glob, glob_index, options) {
+          tokens.push(Token::Sequence(sequences.len() as u32));
+          sequences.push(sequence);
+          glob_index = end;
+          continue;
+        }
+
+        if depth == options.max_brace_depth {
+          return Err(GlobError::new(ErrorKind::NestingTooDeep, glob_index, 1));
+        }
+
+        braces.push(Group::new(tokens.len(), glob_index, depth));
+        depth += 1;
+        tokens.push(Token::Open { next: 0 });
+        glob_index += 1;
+      }
+      b',' | b'}' | b'|' | b')'
+        if braces.last().is_some_and(|group| {
+          matches!(tokens[group.open], Token::Open { .. })
+            == matches!(glob[glob_index], b',' | b'}')
+        }) =>
+      {
+        let index = tokens.len();
+        let group = braces.last_mut().unwrap();
+        match &mut tokens[group.last] {
+          Token::Open { next } | Token::Extglob { next, .. } | Token::Branch { next, .. } => {
+            *next = index as u32
+          }
+          _ => unreachable!(),
+        }
+
+        let is_brace = matches!(tokens[group.open], Token::Open { .. });
+        group.max_depth = group.max_depth.max(depth);
+
+        if matches!(glob[glob_index], b',' | b'|') {
+          tokens.push(Token::Branch { next: 0, end: 0 });
+          group.last = index;
+          depth = group.depth + 1;
+        } else {
+          // After a brace, the engine still has every brace entered by the alternative
+          // taken, while an extglob group leaves the braces as they were before it.
+          depth = if is_brace {
+            group.max_depth
+          } else {
+            group.depth
+          };
+
+          let open = group.open;
+          tokens.push(Token::Close);
+          close_brace(tokens, open, index);
+          braces.pop();
+
+          if let Token::Extglob { .. } = tokens[open] {
+            let has_separator = tokens[open..]
+              .iter()
+              .any(|token| matches!(token, Token::Separator | Token::Globstar { .. }));
+            if let Token::Extglob { multi_segment, .. } = &mut tokens[open] {
+              *multi_segment = has_separator;
+            }
+          }
+        }
+        glob_index += 1;
+      }
+      _ => push_literal(tokens, glob, &mut glob_index, options)?,
+    }
+  }
+
+  if let Some(group) = braces.last() {
+    return Err(match tokens[group.open] {
+      Token::Open { .. } => GlobError::new(ErrorKind::UnclosedBrace, group.offset, 1),
+      _ => GlobError::new(ErrorKind::UnclosedGroup, group.offset, 2),
+    });
+  }
+
+  Ok(())
+}
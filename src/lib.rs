@@ -18,19 +18,41 @@
 //! assert!(glob_match(glob, path));
 //! ```
 //!
+//! When the same pattern is matched against many paths, compile it once with [`Glob`]:
+//!
+//! ```rust
+//! use fast_glob::Glob;
+//!
+//! let glob = Glob::new("some/**/n*d[k-m]e?txt").unwrap();
+//!
+//! assert!(glob.is_match("some/a/bigger/path/to/the/crazy/needle.txt"));
+//! assert!(glob.is_match("some/needle.txt"));
+//! ```
+//!
+//! To find which of many patterns match a path, use [`GlobSet`]:
+//!
+//! ```rust
+//! use fast_glob::GlobSet;
+//!
+//! let set = GlobSet::new(["src/**/*.ts", "src/*.rs", "**/*.md"]).unwrap();
+//!
+//! assert_eq!(set.matches("src/docs/README.md"), vec![2]);
+//! assert_eq!(set.first_match("src/lib.rs"), Some(1));
+//! ```
+//!
 //! ## Syntax
 //!
 //! `fast-glob` supports the following glob pattern syntax:
 //!
-//! | Syntax  | Meaning                                                                                                                                                                                             |
-//! | ------- | --------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------- |
-//! | `?`     | Matches any single character.                                                                                                                                                                       |
-//! | `*`     | Matches zero or more characters, except for path separators (e.g., `/`).                                                                                                                             |
-//! | `**`    | Matches zero or more characters, including path separators. Must match a complete path segment (i.e., followed by a `/` or the end of the pattern).                                                  |
-//! | `[ab]`  | Matches one of the characters contained in the brackets. Character ranges, e.g., `[a-z]`, are also supported. Use `[!ab]` or `[^ab]` to match any character _except_ those contained in the brackets. |
-//! | `{a,b}` | Matches one of the patterns contained in the braces. Any of the wildcard characters can be used in the sub-patterns. Braces may be nested up to 10 levels deep.                                     |
-//! | `!`     | When at the start of the glob, this negates the result. Multiple `!` characters negate the glob multiple times.                                                                                     |
-//! | `\`     | A backslash character may be used to escape any of the above special characters.                                                                                                                    |
+//! | Syntax  | Meaning                                                                                                                                                                                                                                                                                      |
+//! | ------- | -------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------------- |
+//! | `?`     | Matches any single character.                                                                                                                                                                                                                                                                |
+//! | `*`     | Matches zero or more characters, except for path separators (e.g., `/`). With `MatchOptions::literal_separator` unset, it matches separators too.                                                                                                                                            |
+//! | `**`    | Matches zero or more characters, including path separators. Must match a complete path segment (i.e., followed by a `/` or the end of the pattern).                                                                                                                                          |
+//! | `[ab]`  | Matches one of the characters contained in the brackets. Character ranges, e.g., `[a-z]`, are also supported. Use `[!ab]` or `[^ab]` to match any character _except_ those contained in the brackets. POSIX classes such as `[[:alpha:]]` and `[[:digit:]]` can be used inside the brackets. |
+//! | `{a,b}` | Matches one of the patterns contained in the braces. Any of the wildcard characters can be used in the sub-patterns. Braces can be nested, up to the depth set by `MatchOptions::max_brace_depth`. Sequences such as `{1..10}`, `{01..20..2}` or `{a..e}` match any value they expand to.    |
+//! | `!`     | When at the start of the glob, this negates the result. Multiple `!` characters negate the glob multiple times.                                                                                                                                                                              |
+//! | `\`     | A backslash character may be used to escape any of the above special characters. Escaping can be turned off with `MatchOptions::backslash_escape`.                                                                                                                                           |
 //!
 //! ---
 //!
@@ -38,364 +60,62 @@
 //!
 //! For any issues or contributions, please visit the [GitHub repository](https://github.com/shulaoda/fast-glob).
 
-/**
- * The following code is modified based on
- * https://github.com/devongovett/glob-match/blob/d5a6c67/src/lib.rs
- *
- * MIT Licensed
- * Copyright (c) 2023 Devon Govett
- * https://github.com/devongovett/glob-match/tree/main/LICENSE
- */
-use std::path::is_separator;
-
-use arrayvec::ArrayVec;
-
-#[derive(Clone, Debug, Default)]
-struct State {
-  path_index: usize,
-  glob_index: usize,
-  brace_depth: usize,
-
-  wildcard: Wildcard,
-  globstar: Wildcard,
-}
-
-#[derive(Clone, Copy, Debug, Default)]
-struct Wildcard {
-  glob_index: u32,
-  path_index: u32,
-  brace_depth: u32,
-}
-
-type BraceStack = ArrayVec<(u32, u32), 10>;
-
+mod engine;
+mod error;
+mod escape;
+mod expand;
+pub mod gitignore;
+mod glob;
+mod list;
+mod options;
+mod regex;
+mod set;
+mod walk;
+
+pub use error::{ErrorKind, GlobError};
+pub use escape::{escape, escape_path};
+pub use expand::{expand_braces, expand_braces_with_limit, DEFAULT_EXPANSION_LIMIT};
+pub use glob::{Capture, DescendantMatch, Glob};
+pub use list::GlobList;
+pub use options::{MatchOptions, Separator};
+pub use regex::to_regex;
+pub use set::GlobSet;
+pub use walk::{
+  walk, walk_parallel, walk_parallel_with_options, walk_with_options, Entry, Walk, WalkParallel,
+  WalkParallelIter,
+};
+
+use std::ops::Range;
+
+/// Returns whether `path` matches the glob pattern `glob`.
+///
+/// The pattern is parsed on every call; use [`Glob`] to match the same pattern against
+/// many paths. A malformed pattern never matches; use [`Glob::new`] to find out why.
 pub fn glob_match(glob: impl AsRef<[u8]>, path: impl AsRef<[u8]>) -> bool {
-  let glob = glob.as_ref();
-  let path = path.as_ref();
-
-  let mut state = State::default();
-
-  let mut negated = false;
-  while state.glob_index < glob.len() && glob[state.glob_index] == b'!' {
-    negated = !negated;
-    state.glob_index += 1;
-  }
-
-  let mut brace_stack = ArrayVec::<_, 10>::new();
-  let matched = state.glob_match_from(glob, path, 0, &mut brace_stack);
-
-  negated ^ matched
-}
-
-#[inline(always)]
-fn unescape(c: &mut u8, glob: &[u8], state: &mut State) -> bool {
-  if *c == b'\\' {
-    state.glob_index += 1;
-    if state.glob_index >= glob.len() {
-      return false;
-    }
-    *c = match glob[state.glob_index] {
-      b'a' => b'\x61',
-      b'b' => b'\x08',
-      b'n' => b'\n',
-      b'r' => b'\r',
-      b't' => b'\t',
-      c => c,
-    }
-  }
-  true
+  glob::match_lossy(glob.as_ref(), path.as_ref())
 }
 
-impl State {
-  #[inline(always)]
-  fn backtrack(&mut self) {
-    self.glob_index = self.wildcard.glob_index as usize;
-    self.path_index = self.wildcard.path_index as usize;
-    self.brace_depth = self.wildcard.brace_depth as usize;
-  }
-
-  #[inline(always)]
-  fn skip_globstars(&mut self, glob: &[u8]) {
-    let mut glob_index = self.glob_index + 2;
-
-    while glob_index + 4 <= glob.len() && &glob[glob_index..glob_index + 4] == b"/**/" {
-      glob_index += 3;
-    }
-
-    if &glob[glob_index..] == b"/**" {
-      glob_index += 3;
-    }
-
-    self.glob_index = glob_index - 2;
-  }
-
-  #[inline(always)]
-  fn skip_to_separator(&mut self, path: &[u8], is_end_invalid: bool) {
-    if self.path_index == path.len() {
-      self.wildcard.path_index += 1;
-      return;
-    }
-
-    let mut path_index = self.path_index;
-    while path_index < path.len() && !is_separator(path[path_index] as char) {
-      path_index += 1;
-    }
-
-    if is_end_invalid || path_index != path.len() {
-      path_index += 1;
-    }
-
-    self.wildcard.path_index = path_index as u32;
-    self.globstar = self.wildcard;
-  }
-
-  #[inline(always)]
-  fn skip_branch(&mut self, glob: &[u8]) {
-    let mut in_brackets = false;
-    let end_brace_depth = self.brace_depth - 1;
-    while self.glob_index < glob.len() {
-      match glob[self.glob_index] {
-        b'{' if !in_brackets => self.brace_depth += 1,
-        b'}' if !in_brackets => {
-          self.brace_depth -= 1;
-          if self.brace_depth == end_brace_depth {
-            self.glob_index += 1;
-            return;
-          }
-        }
-        b'[' if !in_brackets => in_brackets = true,
-        b']' => in_brackets = false,
-        b'\\' => self.glob_index += 1,
-        _ => (),
-      }
-      self.glob_index += 1;
-    }
-  }
-
-  fn match_brace_branch(
-    &self,
-    glob: &[u8],
-    path: &[u8],
-    open_brace_index: usize,
-    branch_index: usize,
-    brace_stack: &mut BraceStack,
-  ) -> bool {
-    brace_stack.push((open_brace_index as u32, branch_index as u32));
-
-    let mut branch_state = self.clone();
-    branch_state.glob_index = branch_index;
-    branch_state.brace_depth = brace_stack.len();
-
-    let matched = branch_state.glob_match_from(glob, path, branch_index, brace_stack);
-
-    brace_stack.pop();
-
-    matched
-  }
-
-  fn match_brace(&mut self, glob: &[u8], path: &[u8], brace_stack: &mut BraceStack) -> bool {
-    let mut brace_depth = 0;
-    let mut in_brackets = false;
-
-    let open_brace_index = self.glob_index;
-
-    let mut branch_index = 0;
-
-    while self.glob_index < glob.len() {
-      match glob[self.glob_index] {
-        b'{' if !in_brackets => {
-          brace_depth += 1;
-          if brace_depth == 1 {
-            branch_index = self.glob_index + 1;
-          }
-        }
-        b'}' if !in_brackets => {
-          brace_depth -= 1;
-          if brace_depth == 0 {
-            if self.match_brace_branch(glob, path, open_brace_index, branch_index, brace_stack) {
-              return true;
-            }
-            break;
-          }
-        }
-        b',' if brace_depth == 1 => {
-          if self.match_brace_branch(glob, path, open_brace_index, branch_index, brace_stack) {
-            return true;
-          }
-          branch_index = self.glob_index + 1;
-        }
-        b'[' if !in_brackets => in_brackets = true,
-        b']' => in_brackets = false,
-        b'\\' => self.glob_index += 1,
-        _ => (),
-      }
-      self.glob_index += 1;
-    }
-
-    false
-  }
-
-  #[inline(always)]
-  fn glob_match_from(
-    &mut self,
-    glob: &[u8],
-    path: &[u8],
-    match_start: usize,
-    brace_stack: &mut BraceStack,
-  ) -> bool {
-    while self.glob_index < glob.len() || self.path_index < path.len() {
-      if self.glob_index < glob.len() {
-        match glob[self.glob_index] {
-          b'*' => {
-            let is_globstar = self.glob_index + 1 < glob.len() && glob[self.glob_index + 1] == b'*';
-            if is_globstar {
-              self.skip_globstars(glob);
-            }
-
-            self.wildcard.glob_index = self.glob_index as u32;
-            self.wildcard.path_index = self.path_index as u32 + 1;
-            self.wildcard.brace_depth = self.brace_depth as u32;
-
-            let mut in_globstar = false;
-            if is_globstar {
-              self.glob_index += 2;
-
-              let is_end_invalid = self.glob_index != glob.len();
-
-              if (self.glob_index.saturating_sub(match_start) < 3
-                || glob[self.glob_index - 3] == b'/')
-                && (!is_end_invalid || glob[self.glob_index] == b'/')
-              {
-                if is_end_invalid {
-                  self.glob_index += 1;
-                }
-
-                self.skip_to_separator(path, is_end_invalid);
-                in_globstar = true;
-              }
-            } else {
-              self.glob_index += 1;
-            }
-
-            if !in_globstar
-              && self.path_index < path.len()
-              && is_separator(path[self.path_index] as char)
-            {
-              self.wildcard = self.globstar;
-            }
-
-            continue;
-          }
-          b'?' if self.path_index < path.len() => {
-            if !is_separator(path[self.path_index] as char) {
-              self.glob_index += 1;
-              self.path_index += 1;
-              continue;
-            }
-          }
-          b'[' if self.path_index < path.len() => {
-            self.glob_index += 1;
-
-            let mut negated = false;
-            if self.glob_index < glob.len() && matches!(glob[self.glob_index], b'^' | b'!') {
-              negated = true;
-              self.glob_index += 1;
-            }
-
-            let mut first = true;
-            let mut is_match = false;
-            let c = path[self.path_index];
-            while self.glob_index < glob.len() && (first || glob[self.glob_index] != b']') {
-              let mut low = glob[self.glob_index];
-              if !unescape(&mut low, glob, self) {
-                return false;
-              }
-
-              self.glob_index += 1;
-
-              let high = if self.glob_index + 1 < glob.len()
-                && glob[self.glob_index] == b'-'
-                && glob[self.glob_index + 1] != b']'
-              {
-                self.glob_index += 1;
-
-                let mut high = glob[self.glob_index];
-                if !unescape(&mut high, glob, self) {
-                  return false;
-                }
-
-                self.glob_index += 1;
-                high
-              } else {
-                low
-              };
-
-              if low <= c && c <= high {
-                is_match = true;
-              }
-
-              first = false;
-            }
-
-            if self.glob_index >= glob.len() {
-              return false;
-            }
-
-            self.glob_index += 1;
-            if is_match != negated {
-              self.path_index += 1;
-              continue;
-            }
-          }
-          b'{' => {
-            if let Some((_, branch_index)) = brace_stack
-              .iter()
-              .find(|(open_brace_index, _)| *open_brace_index == self.glob_index as u32)
-            {
-              self.glob_index = *branch_index as usize;
-              self.brace_depth += 1;
-              continue;
-            }
-            return self.match_brace(glob, path, brace_stack);
-          }
-          b',' | b'}' if self.brace_depth > 0 => {
-            self.skip_branch(glob);
-            continue;
-          }
-          mut c if self.path_index < path.len() => {
-            if !unescape(&mut c, glob, self) {
-              return false;
-            }
-
-            let is_match = if c == b'/' {
-              is_separator(path[self.path_index] as char)
-            } else {
-              path[self.path_index] == c
-            };
-
-            if is_match {
-              self.glob_index += 1;
-              self.path_index += 1;
-
-              if c == b'/' {
-                self.wildcard = self.globstar;
-              }
-
-              continue;
-            }
-          }
-          _ => {}
-        }
-      }
-
-      if self.wildcard.path_index > 0 && self.wildcard.path_index <= path.len() as u32 {
-        self.backtrack();
-        continue;
-      }
-
-      return false;
-    }
-
-    true
-  }
+/// Matches `path` against `glob` and returns the byte ranges of `path` matched by each
+/// wildcard, character class and brace of the pattern, or `None` if it doesn't match.
+///
+/// See [`Glob::captures`] for details.
+///
+/// ```rust
+/// use fast_glob::glob_match_with_captures;
+///
+/// let path = "some/a/bigger/path/to/the/crazy/needle.txt";
+/// let captures = glob_match_with_captures("some/**/{tob,crazy}/?*.{png,txt}", path).unwrap();
+///
+/// assert_eq!(&path[captures[0].clone()], "a/bigger/path/to/the");
+/// assert_eq!(&path[captures[1].clone()], "crazy");
+/// assert_eq!(&path[captures[2].clone()], "n");
+/// assert_eq!(&path[captures[3].clone()], "eedle");
+/// assert_eq!(&path[captures[4].clone()], "txt");
+/// ```
+pub fn glob_match_with_captures(
+  glob: impl AsRef<[u8]>,
+  path: impl AsRef<[u8]>,
+) -> Option<Vec<Range<usize>>> {
+  let captures = Glob::new_lossy(glob.as_ref()).captures(path)?;
+  Some(captures.into_iter().map(|capture| capture.range).collect())
 }
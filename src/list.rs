@@ -0,0 +1,96 @@
+use crate::{Glob, GlobError, GlobSet, MatchOptions};
+
+/// An ordered list of patterns including and excluding paths, where the last matching
+/// pattern wins.
+///
+/// A pattern starting with `!` excludes the paths it matches, and any other pattern
+/// includes them. A path is matched by the list if the last pattern matching it includes
+/// it, so later patterns can exclude part of what earlier ones include and the other way
+/// around. A path no pattern matches isn't matched by the list.
+///
+/// ```rust
+/// use fast_glob::GlobList;
+///
+/// let list = GlobList::new(["src/**", "!src/**/*.test.ts", "src/keep.test.ts"]).unwrap();
+///
+/// assert!(list.is_match("src/index.ts"));
+/// assert!(!list.is_match("src/a/index.test.ts"));
+/// assert!(list.is_match("src/keep.test.ts"));
+/// assert!(!list.is_match("docs/index.md"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GlobList {
+  /// The patterns without their `!`, last pattern first.
+  set: GlobSet,
+  /// Whether each pattern excludes the paths it matches, in the order of the patterns.
+  excludes: Vec<bool>,
+}
+
+impl GlobList {
+  /// Compiles the ordered `patterns` into a list.
+  pub fn new<I>(patterns: I) -> Result<GlobList, GlobError>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+  {
+    GlobList::with_options(patterns, MatchOptions::default())
+  }
+
+  /// Compiles the ordered `patterns` into a list, with the given options applied to
+  /// every pattern.
+  pub fn with_options<I>(patterns: I, options: MatchOptions) -> Result<GlobList, GlobError>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+  {
+    let mut globs = patterns
+      .into_iter()
+      .map(|pattern| Glob::with_options(pattern, options))
+      .collect::<Result<Vec<_>, _>>()?;
+
+    let excludes = globs.iter().map(|glob| glob.negated).collect();
+    globs.reverse();
+    let set = globs
+      .into_iter()
+      .map(|mut glob| {
+        glob.negated = false;
+        glob
+      })
+      .collect();
+
+    Ok(GlobList { set, excludes })
+  }
+
+  /// Returns the number of patterns in this list.
+  pub fn len(&self) -> usize {
+    self.excludes.len()
+  }
+
+  /// Returns whether this list contains no patterns.
+  pub fn is_empty(&self) -> bool {
+    self.excludes.is_empty()
+  }
+
+  /// Returns whether the last pattern matching `path` includes it.
+  pub fn is_match(&self, path: impl AsRef<[u8]>) -> bool {
+    self
+      .last_match(path)
+      .is_some_and(|index| !self.excludes[index])
+  }
+
+  /// Returns the index of the last pattern matching `path`, which decides whether the
+  /// list matches it.
+  ///
+  /// ```rust
+  /// use fast_glob::GlobList;
+  ///
+  /// let list = GlobList::new(["**/*.ts", "!**/*.d.ts"]).unwrap();
+  ///
+  /// assert_eq!(list.last_match("src/index.d.ts"), Some(1));
+  /// assert_eq!(list.last_match("README.md"), None);
+  /// ```
+  pub fn last_match(&self, path: impl AsRef<[u8]>) -> Option<usize> {
+    let index = self.set.first_match(path)?;
+    Some(self.len() - 1 - index)
+  }
+}
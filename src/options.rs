@@ -0,0 +1,259 @@
+/// Options controlling how a glob is compiled and matched.
+///
+/// The defaults give the same behavior as [`glob_match`](crate::glob_match).
+///
+/// ```rust
+/// use fast_glob::{Glob, MatchOptions};
+///
+/// let options = MatchOptions::new().case_insensitive(true);
+/// let glob = Glob::with_options("*.PNG", options).unwrap();
+///
+/// assert!(glob.is_match("photo.png"));
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MatchOptions {
+  pub(crate) case_insensitive: bool,
+  pub(crate) utf8: bool,
+  pub(crate) extglob: bool,
+  pub(crate) max_brace_depth: usize,
+  pub(crate) separator: Separator,
+  pub(crate) dot: bool,
+  pub(crate) literal_separator: bool,
+  pub(crate) backslash_escape: bool,
+  pub(crate) basename: bool,
+}
+
+/// The characters separating the segments of a path, which `*`, `?` and `[...]` never
+/// match and which a `/` in a pattern matches. See [`MatchOptions::separator`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Separator {
+  /// Only `/`.
+  Slash,
+  /// Only `\`.
+  Backslash,
+  /// Both `/` and `\`.
+  Both,
+}
+
+impl Separator {
+  pub(crate) fn bytes(self) -> [u8; 2] {
+    match self {
+      Separator::Slash => [b'/', b'/'],
+      Separator::Backslash => [b'\\', b'\\'],
+      Separator::Both => [b'/', b'\\'],
+    }
+  }
+}
+
+impl Default for Separator {
+  /// Returns the separators of the host, which are [`Separator::Both`] on Windows and
+  /// [`Separator::Slash`] elsewhere.
+  fn default() -> Separator {
+    if cfg!(windows) {
+      Separator::Both
+    } else {
+      Separator::Slash
+    }
+  }
+}
+
+impl Default for MatchOptions {
+  fn default() -> MatchOptions {
+    MatchOptions {
+      case_insensitive: false,
+      utf8: false,
+      extglob: false,
+      max_brace_depth: 64,
+      separator: Separator::default(),
+      dot: true,
+      literal_separator: true,
+      backslash_escape: true,
+      basename: false,
+    }
+  }
+}
+
+impl MatchOptions {
+  /// Returns the default options.
+  pub fn new() -> MatchOptions {
+    MatchOptions::default()
+  }
+
+  /// Whether letters match regardless of case, in literals, character classes and brace
+  /// alternatives alike. Non-ASCII letters are compared with simple Unicode case folding.
+  ///
+  /// Defaults to `false`.
+  pub fn case_insensitive(mut self, yes: bool) -> MatchOptions {
+    self.case_insensitive = yes;
+    self
+  }
+
+  /// Whether patterns and paths are read as UTF-8, so that `?` and `[...]` match a whole
+  /// character rather than a single byte, and classes can contain non-ASCII characters
+  /// and ranges. Bytes that aren't valid UTF-8 are matched one at a time, as if each were
+  /// a U+FFFD replacement character.
+  ///
+  /// Defaults to `false`.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions};
+  ///
+  /// let options = MatchOptions::new().utf8(true);
+  ///
+  /// assert!(Glob::with_options("caf?", options).unwrap().is_match("café"));
+  /// assert!(Glob::with_options("[à-ÿ]t[é]", options).unwrap().is_match("été"));
+  /// ```
+  pub fn utf8(mut self, yes: bool) -> MatchOptions {
+    self.utf8 = yes;
+    self
+  }
+
+  /// Whether the extglob groups of bash and micromatch are recognized:
+  ///
+  /// - `@(a|b)` matches one of the alternatives.
+  /// - `?(a|b)` matches zero or one of them.
+  /// - `*(a|b)` matches any number of them.
+  /// - `+(a|b)` matches one or more of them.
+  /// - `!(a|b)` matches anything except one of them.
+  ///
+  /// A group only matches across path separators if one of its alternatives contains a
+  /// `/`, so `!(*.test).ts` excludes test files within a single path segment. When this is
+  /// set, a leading `!(` is a group rather than a negation of the whole pattern.
+  ///
+  /// Defaults to `false`.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions};
+  ///
+  /// let options = MatchOptions::new().extglob(true);
+  /// let glob = Glob::with_options("@(src|lib)/**/!(*.test).ts", options).unwrap();
+  ///
+  /// assert!(glob.is_match("src/a/index.ts"));
+  /// assert!(!glob.is_match("src/a/index.test.ts"));
+  /// assert!(!glob.is_match("tests/index.ts"));
+  /// ```
+  pub fn extglob(mut self, yes: bool) -> MatchOptions {
+    self.extglob = yes;
+    self
+  }
+
+  /// The most braces a pattern may enter at once, counting both nested braces and the
+  /// braces before them, so `{a,{b,c}}` enters 2 and `{a,b}{c,d}{e,f}` enters 3. Extglob
+  /// groups count as braces, so `@(a|@(b))` enters 2 too. A pattern entering more fails to
+  /// compile with [`ErrorKind::NestingTooDeep`], and never matches with
+  /// [`glob_match`](crate::glob_match).
+  ///
+  /// The engine recurses into each brace and group, so this bounds the stack used by
+  /// matching. It doesn't bound the time taken, which stays polynomial at any depth since
+  /// the engine remembers where the rest of the pattern failed to match after a group.
+  ///
+  /// Defaults to 64.
+  ///
+  /// [`ErrorKind::NestingTooDeep`]: crate::ErrorKind::NestingTooDeep
+  pub fn max_brace_depth(mut self, depth: usize) -> MatchOptions {
+    self.max_brace_depth = depth;
+    self
+  }
+
+  /// Which characters separate path segments. A `/` in a pattern matches any of them,
+  /// whatever the host, so the same pattern can match Windows paths on Linux.
+  ///
+  /// Defaults to the separators of the host, see [`Separator::default`].
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions, Separator};
+  ///
+  /// let options = MatchOptions::new().separator(Separator::Both);
+  /// let glob = Glob::with_options("src/*/*.rs", options).unwrap();
+  ///
+  /// assert!(glob.is_match("src\\bin\\main.rs"));
+  /// assert!(!glob.is_match("src\\main.rs"));
+  /// ```
+  pub fn separator(mut self, separator: Separator) -> MatchOptions {
+    self.separator = separator;
+    self
+  }
+
+  /// Whether wildcards match path segments starting with a `.`, such as `.git`. When
+  /// unset, `*`, `?`, `[...]` and extglob groups don't match the dot starting a segment and
+  /// `**` doesn't match segments starting with a dot, so hidden files are only matched by
+  /// a pattern segment starting with an explicit `.`.
+  ///
+  /// Defaults to `true`.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions};
+  ///
+  /// let options = MatchOptions::new().dot(false);
+  ///
+  /// assert!(!Glob::with_options("**/*", options).unwrap().is_match(".git/config"));
+  /// assert!(Glob::with_options(".git/*", options).unwrap().is_match(".git/config"));
+  /// ```
+  pub fn dot(mut self, yes: bool) -> MatchOptions {
+    self.dot = yes;
+    self
+  }
+
+  /// Whether path separators are only matched by a separator in the pattern. When unset,
+  /// `*` and `?` match separators too, so `*` spans any number of path segments as in
+  /// Python's `fnmatch`, which suits keys such as S3 object names where `/` is just
+  /// another character. With [`MatchOptions::dot`] unset, a `*` spanning segments still
+  /// doesn't match the leading dot of any of them.
+  ///
+  /// Defaults to `true`.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions};
+  ///
+  /// let options = MatchOptions::new().literal_separator(false);
+  /// let glob = Glob::with_options("logs/*.gz", options).unwrap();
+  ///
+  /// assert!(glob.is_match("logs/2024/01/app.gz"));
+  /// assert!(!Glob::new("logs/*.gz").unwrap().is_match("logs/2024/01/app.gz"));
+  /// ```
+  pub fn literal_separator(mut self, yes: bool) -> MatchOptions {
+    self.literal_separator = yes;
+    self
+  }
+
+  /// Whether a `\` in a pattern escapes the character after it. When unset, a `\` is a
+  /// literal, or a path separator if [`MatchOptions::separator`] includes `\`, so
+  /// patterns can be written with Windows paths.
+  ///
+  /// Defaults to `true`.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions, Separator};
+  ///
+  /// let options = MatchOptions::new()
+  ///   .backslash_escape(false)
+  ///   .separator(Separator::Both);
+  /// let glob = Glob::with_options("src\\**\\*.rs", options).unwrap();
+  ///
+  /// assert!(glob.is_match("src\\bin\\main.rs"));
+  /// assert!(glob.is_match("src/lib.rs"));
+  /// ```
+  pub fn backslash_escape(mut self, yes: bool) -> MatchOptions {
+    self.backslash_escape = yes;
+    self
+  }
+
+  /// Whether a pattern without path separators is matched against the last segment of
+  /// paths, like `matchBase` in minimatch, so that `*.rs` matches `src/lib.rs`. Patterns
+  /// containing a separator still match whole paths.
+  ///
+  /// Defaults to `false`.
+  ///
+  /// ```rust
+  /// use fast_glob::{Glob, MatchOptions};
+  ///
+  /// let options = MatchOptions::new().basename(true);
+  ///
+  /// assert!(Glob::with_options("*.rs", options).unwrap().is_match("src/lib.rs"));
+  /// assert!(!Glob::with_options("src/*.rs", options).unwrap().is_match("src/bin/main.rs"));
+  /// ```
+  pub fn basename(mut self, yes: bool) -> MatchOptions {
+    self.basename = yes;
+    self
+  }
+}
@@ -0,0 +1,614 @@
+use std::{ops::Range, sync::OnceLock};
+
+use crate::{
+  engine::fold_case,
+  glob::{parse_posix_class, parse_sequence, ExtglobKind, Sequence, Token},
+  ErrorKind, Glob, GlobError, MatchOptions, Separator, DEFAULT_EXPANSION_LIMIT,
+};
+
+/// Matches any character.
+const ANY: &str = r"[\s\S]";
+/// Matches no character at all.
+const NEVER: &str = r"[^\s\S]";
+
+/// Converts the glob pattern `glob` into a regular expression matching the same paths
+/// with the given options, anchored with `^` and `$`.
+///
+/// The regular expression matches text one character at a time, so it agrees with the
+/// glob compiled with [`MatchOptions::utf8`] set, whatever `options` say. On ASCII paths,
+/// that is no different from the glob itself.
+///
+/// The syntax is the common subset of PCRE, JavaScript with the `u` flag and the `regex`
+/// crate. Negated patterns and wildcards that can't match a leading dot with
+/// [`MatchOptions::dot`] unset need lookaround assertions, which the `regex` crate doesn't
+/// support.
+///
+/// Returns an error for a malformed pattern, as [`Glob::with_options`] does, and an
+/// [`ErrorKind::Unsupported`] error for a `!(...)` extglob group, which no regular
+/// expression of this syntax can express, or for a sequence brace expanding to more than
+/// [`DEFAULT_EXPANSION_LIMIT`] values.
+///
+/// ```rust
+/// use fast_glob::{to_regex, MatchOptions};
+///
+/// let regex = to_regex("src/**/*.{ts,js}", MatchOptions::new()).unwrap();
+///
+/// assert_eq!(regex, r"^src/(?:[^/]*/)*[^/]*\.(?:ts|js)$");
+/// ```
+pub fn to_regex(glob: impl AsRef<str>, options: MatchOptions) -> Result<String, GlobError> {
+  let pattern = glob.as_ref().as_bytes();
+  let glob = Glob::with_options(pattern, options.utf8(true))?;
+
+  let (separator, non_separator) = match options.separator {
+    Separator::Slash => ("/", "[^/]"),
+    Separator::Backslash => (r"\\", r"[^\\]"),
+    Separator::Both => (r"[/\\]", r"[^/\\]"),
+  };
+
+  let mut translator = Translator {
+    glob: &glob,
+    pattern,
+    separator,
+    non_separator,
+    regex: String::new(),
+  };
+
+  if glob.basename {
+    // The pattern matches the last segment, and can't match separators itself.
+    translator
+      .regex
+      .push_str(&format!("(?:{ANY}*{separator})?"));
+  }
+  translator.translate(0, glob.tokens.len(), Position::SegmentStart, glob.basename)?;
+
+  let body = translator.regex;
+  Ok(if glob.negated {
+    format!("^(?!{body}$){ANY}*$")
+  } else {
+    format!("^{body}$")
+  })
+}
+
+/// What is known at some point of the regular expression about where it is in a path
+/// segment, which decides how a leading dot is ruled out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Position {
+  /// At the start of the path or right after a separator.
+  SegmentStart,
+  /// After a character that isn't a separator.
+  InSegment,
+  /// Either of them.
+  Unknown,
+}
+
+impl Position {
+  fn join(self, other: Position) -> Position {
+    if self == other {
+      self
+    } else {
+      Position::Unknown
+    }
+  }
+}
+
+struct Translator<'a> {
+  glob: &'a Glob,
+  pattern: &'a [u8],
+  /// Matches one path separator.
+  separator: &'static str,
+  /// Matches one character other than a path separator.
+  non_separator: &'static str,
+  regex: String,
+}
+
+impl Translator<'_> {
+  /// Translates `tokens[start..stop]`, starting at `position`, and returns the position it
+  /// ends at. If `no_separator` is set, the tokens can't match path separators, as in an
+  /// extglob group confined to a path segment.
+  fn translate(
+    &mut self,
+    start: usize,
+    stop: usize,
+    mut position: Position,
+    no_separator: bool,
+  ) -> Result<Position, GlobError> {
+    let tokens = &self.glob.tokens;
+    let options = &self.glob.options;
+    let separators = options.separator.bytes();
+    let wildcard = if options.literal_separator {
+      self.non_separator
+    } else {
+      ANY
+    };
+
+    let mut index = start;
+    while index < stop {
+      match tokens[index] {
+        Token::Byte(_) => {
+          let end = tokens[index..stop]
+            .iter()
+            .position(|token| !matches!(token, Token::Byte(_)))
+            .map_or(stop, |len| index + len);
+          let bytes: Vec<u8> = tokens[index..end]
+            .iter()
+            .map(|token| match token {
+              Token::Byte(c) => *c,
+              _ => unreachable!(),
+            })
+            .collect();
+
+          if no_separator && bytes.iter().any(|c| separators.contains(c)) {
+            self.regex.push_str(NEVER);
+          } else {
+            for c in String::from_utf8_lossy(&bytes).chars() {
+              push_literal(&mut self.regex, c);
+            }
+          }
+
+          position = if separators.contains(bytes.last().unwrap()) {
+            Position::SegmentStart
+          } else {
+            Position::InSegment
+          };
+          index = end;
+          continue;
+        }
+        Token::Caseless(c) => {
+          self.regex.push('[');
+          self.regex.push(c as char);
+          self.regex.push(c.to_ascii_uppercase() as char);
+          self.regex.push(']');
+          position = Position::InSegment;
+        }
+        Token::CaselessChar(c) => {
+          let chars: Vec<(u32, u32)> = std::iter::once(c)
+            .chain(
+              case_table()
+                .folds
+                .iter()
+                .filter(|&&(_, f)| f == c)
+                .map(|&(d, _)| d),
+            )
+            .map(|d| (d as u32, d as u32))
+            .collect();
+          push_class(&mut self.regex, false, &chars);
+          position = Position::InSegment;
+        }
+        Token::Separator => {
+          self
+            .regex
+            .push_str(if no_separator { NEVER } else { self.separator });
+          position = Position::SegmentStart;
+        }
+        Token::NoDot => self.push_no_dot(position),
+        Token::Star => {
+          if !options.literal_separator && !options.dot {
+            // The star can span separators, but not the leading dots after them.
+            let non_separator = self.non_separator;
+            self
+              .regex
+              .push_str(&format!(r"(?:(?!(?<!{non_separator})\.){ANY})"));
+          } else {
+            self.regex.push_str(wildcard);
+          }
+          self.regex.push('*');
+          if position != Position::InSegment || !options.literal_separator {
+            position = Position::Unknown;
+          }
+        }
+        Token::Any | Token::AnyChar => {
+          self.regex.push_str(wildcard);
+          position = if options.literal_separator {
+            Position::InSegment
+          } else {
+            Position::Unknown
+          };
+        }
+        Token::Class { negated, len } | Token::CharClass { negated, len, .. } => {
+          let caseless = matches!(tokens[index], Token::CharClass { caseless: true, .. });
+          let mut ranges: Vec<(u32, u32)> = tokens[index + 1..index + 1 + len as usize]
+            .iter()
+            .map(|token| match *token {
+              Token::Range(low, high) => (low, high),
+              _ => unreachable!(),
+            })
+            .collect();
+          if caseless {
+            ranges.extend(case_mappings_into(&ranges));
+          }
+
+          let separators = separators.map(u32::from);
+          let mut can_match_separator = separators
+            .iter()
+            .any(|&c| ranges.iter().any(|&(low, high)| low <= c && c <= high) != negated);
+          if no_separator && can_match_separator {
+            if negated {
+              ranges.extend(separators.map(|c| (c, c)));
+            } else {
+              for c in separators {
+                ranges = subtract(&ranges, c);
+              }
+            }
+            can_match_separator = false;
+          }
+
+          push_class(&mut self.regex, negated, &ranges);
+          position = if can_match_separator {
+            Position::Unknown
+          } else {
+            Position::InSegment
+          };
+          index += 1 + len as usize;
+          continue;
+        }
+        Token::Globstar { end } => {
+          let non_separator = self.non_separator;
+          let separator = self.separator;
+          if options.dot {
+            if end {
+              self.regex.push_str(ANY);
+              self.regex.push('*');
+            } else {
+              self
+                .regex
+                .push_str(&format!("(?:{non_separator}*{separator})*"));
+            }
+          } else {
+            // Each segment the globstar spans must not start with a dot. Past the first
+            // one, the segment starts right after a separator.
+            self.regex.push_str("(?:");
+            self.push_no_dot(position);
+            if end {
+              self.regex.push_str(&format!(
+                r"{non_separator}*(?:{separator}(?!\.){non_separator}*)*)?"
+              ));
+            } else {
+              self.regex.push_str(&format!(
+                r"{non_separator}*{separator}(?:(?!\.){non_separator}*{separator})*)?"
+              ));
+            }
+          }
+
+          if position != Position::SegmentStart {
+            position = Position::Unknown;
+          }
+        }
+        Token::Open { next } => {
+          let (end, positions) =
+            self.translate_alternatives(index, next as usize, position, no_separator)?;
+          self.regex.push(')');
+          position = positions;
+          index = end;
+          continue;
+        }
+        Token::Extglob {
+          kind,
+          multi_segment,
+          next,
+          end,
+        } => {
+          if kind == ExtglobKind::Not {
+            let group = tokens[..index]
+              .iter()
+              .filter(|token| matches!(token, Token::Extglob { .. }))
+              .count();
+            let offset = locate(self.pattern, options).0[group];
+            return Err(GlobError::new(ErrorKind::Unsupported, offset, 2));
+          }
+
+          let no_separator = no_separator || (!multi_segment && options.literal_separator);
+          let repeated = matches!(kind, ExtglobKind::ZeroOrMore | ExtglobKind::OneOrMore);
+          let start = if repeated {
+            Position::Unknown
+          } else {
+            position
+          };
+
+          let (_, positions) =
+            self.translate_alternatives(index, next as usize, start, no_separator)?;
+          self.regex.push(')');
+          // Regex engines reject repeating a group that only matches the empty string, which
+          // its quantifier doesn't change anyway.
+          let is_empty = tokens[index + 1..end as usize].iter().all(|token| {
+            matches!(
+              token,
+              Token::Open { .. } | Token::Branch { .. } | Token::Close | Token::Extglob { .. }
+            )
+          });
+          let quantifier = match kind {
+            _ if is_empty => "",
+            ExtglobKind::One => "",
+            ExtglobKind::ZeroOrOne => "?",
+            ExtglobKind::ZeroOrMore => "*",
+            _ => "+",
+          };
+          self.regex.push_str(quantifier);
+          position = match kind {
+            ExtglobKind::One => positions,
+            ExtglobKind::ZeroOrOne => positions.join(position),
+            _ => Position::Unknown,
+          };
+          index = end as usize;
+          continue;
+        }
+        Token::Sequence(sequence) => {
+          let index = sequence as usize;
+          let sequence = &self.glob.sequences[index];
+          self.push_sequence(sequence, index, no_separator)?;
+          position = if sequence.chars {
+            Position::Unknown
+          } else {
+            Position::InSegment
+          };
+        }
+        Token::Range(..) | Token::Branch { .. } | Token::Close | Token::Invalid => {
+          unreachable!()
+        }
+      }
+
+      index += 1;
+    }
+
+    Ok(position)
+  }
+
+  /// Translates the alternatives of the brace or extglob group opened at `open`, whose
+  /// first alternative ends at `next`, into an unclosed `(?:a|b`. Returns the index just
+  /// past the group along with the position its alternatives end at.
+  fn translate_alternatives(
+    &mut self,
+    open: usize,
+    mut next: usize,
+    position: Position,
+    no_separator: bool,
+  ) -> Result<(usize, Position), GlobError> {
+    self.regex.push_str("(?:");
+    let mut start = open + 1;
+    let mut end_position = self.translate(start, next, position, no_separator)?;
+
+    while let Token::Branch { next: n, .. } = self.glob.tokens[next] {
+      self.regex.push('|');
+      start = next + 1;
+      next = n as usize;
+      let branch_position = self.translate(start, next, position, no_separator)?;
+      end_position = end_position.join(branch_position);
+    }
+
+    Ok((next + 1, end_position))
+  }
+
+  /// Pushes an assertion that the path isn't at a dot starting a path segment.
+  fn push_no_dot(&mut self, position: Position) {
+    match position {
+      Position::SegmentStart => self.regex.push_str(r"(?!\.)"),
+      Position::InSegment => {}
+      Position::Unknown => {
+        let non_separator = self.non_separator;
+        self.regex.push_str(&format!(r"(?!(?<!{non_separator})\.)"));
+      }
+    }
+  }
+
+  /// Pushes an alternation of the values of `sequence`, the `index`th sequence brace.
+  fn push_sequence(
+    &mut self,
+    sequence: &Sequence,
+    index: usize,
+    no_separator: bool,
+  ) -> Result<(), GlobError> {
+    if sequence.chars {
+      let separators = self.glob.options.separator.bytes();
+      let chars: Vec<(u32, u32)> = (0..=0x7fu8)
+        .filter(|&c| {
+          sequence.contains(c as i64)
+            || (sequence.caseless
+              && c.is_ascii_alphabetic()
+              && sequence.contains((c ^ 0x20) as i64))
+        })
+        .filter(|c| !no_separator || !separators.contains(c))
+        .map(|c| (c as u32, c as u32))
+        .collect();
+      push_class(&mut self.regex, false, &chars);
+      return Ok(());
+    }
+
+    let (low, high) = (
+      sequence.start.min(sequence.end),
+      sequence.start.max(sequence.end),
+    );
+    let count = (high as i128 - low as i128) / sequence.step as i128 + 1;
+    if count > DEFAULT_EXPANSION_LIMIT as i128 {
+      let span = locate(self.pattern, &self.glob.options).1[index].clone();
+      return Err(GlobError::new(
+        ErrorKind::Unsupported,
+        span.start,
+        span.len(),
+      ));
+    }
+
+    // The engine reads at most 18 digits, so longer values never match.
+    let step = sequence.step as i128 * (sequence.end as i128 - sequence.start as i128).signum();
+    let values: Vec<String> = (0..count)
+      .map(|i| sequence.start as i128 + i * step)
+      .map(|value| format!("{:0width$}", value, width = sequence.width))
+      .filter(|value| value.trim_start_matches('-').len() <= 18)
+      .collect();
+
+    if values.is_empty() {
+      self.regex.push_str(NEVER);
+    } else {
+      self.regex.push_str("(?:");
+      self.regex.push_str(&values.join("|"));
+      self.regex.push(')');
+    }
+
+    Ok(())
+  }
+}
+
+/// The case mappings of the characters that have one, which are all below U+20000.
+struct CaseTable {
+  /// Each character that [`fold_case`] changes, with its folded case.
+  folds: Vec<(char, char)>,
+  /// Each non-ASCII character with its single-character lowercase or uppercase mapping.
+  mappings: Vec<(char, char)>,
+}
+
+fn case_table() -> &'static CaseTable {
+  static TABLE: OnceLock<CaseTable> = OnceLock::new();
+  TABLE.get_or_init(|| {
+    let single = |chars: &mut dyn Iterator<Item = char>| match (chars.next(), chars.next()) {
+      (Some(c), None) => Some(c),
+      _ => None,
+    };
+
+    let mut table = CaseTable {
+      folds: Vec::new(),
+      mappings: Vec::new(),
+    };
+    for c in (0..0x2_0000).filter_map(char::from_u32) {
+      if fold_case(c) != c {
+        table.folds.push((c, fold_case(c)));
+      }
+      if !c.is_ascii() {
+        let lower = single(&mut c.to_lowercase());
+        let upper = single(&mut c.to_uppercase());
+        for mapped in [lower, upper].into_iter().flatten() {
+          if mapped != c {
+            table.mappings.push((c, mapped));
+          }
+        }
+      }
+    }
+
+    table
+  })
+}
+
+/// Returns the non-ASCII characters whose single-character lowercase or uppercase mapping
+/// is in `ranges`, which a caseless class also matches.
+fn case_mappings_into(ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+  case_table()
+    .mappings
+    .iter()
+    .filter(|&&(_, m)| {
+      ranges
+        .iter()
+        .any(|&(low, high)| low <= m as u32 && m as u32 <= high)
+    })
+    .map(|&(c, _)| (c as u32, c as u32))
+    .collect()
+}
+
+/// Removes the code point `c` from `ranges`.
+fn subtract(ranges: &[(u32, u32)], c: u32) -> Vec<(u32, u32)> {
+  let mut result = Vec::with_capacity(ranges.len() + 1);
+  for &(low, high) in ranges {
+    if c < low || c > high {
+      result.push((low, high));
+      continue;
+    }
+
+    if low < c {
+      result.push((low, c - 1));
+    }
+    if c < high {
+      result.push((c + 1, high));
+    }
+  }
+
+  result
+}
+
+/// Pushes the character `c`, escaped if it means something in a regular expression.
+fn push_literal(regex: &mut String, c: char) {
+  match c {
+    '\\' | '^' | '$' | '.' | '|' | '?' | '*' | '+' | '(' | ')' | '[' | ']' | '{' | '}' => {
+      regex.push('\\');
+      regex.push(c);
+    }
+    c if c.is_ascii_control() => regex.push_str(&format!(r"\x{:02x}", c as u32)),
+    c => regex.push(c),
+  }
+}
+
+/// Pushes a class matching the code points in `ranges`, or any other if `negated` is set.
+fn push_class(regex: &mut String, negated: bool, ranges: &[(u32, u32)]) {
+  if ranges.is_empty() {
+    regex.push_str(if negated { ANY } else { NEVER });
+    return;
+  }
+
+  // Punctuation is written as a hex escape, since each syntax gives meaning to a
+  // different set of it inside classes.
+  let push_char = |regex: &mut String, c: u32| match char::from_u32(c) {
+    Some(c) if c.is_ascii_alphanumeric() || !c.is_ascii() => regex.push(c),
+    _ => regex.push_str(&format!(r"\x{c:02x}")),
+  };
+
+  regex.push('[');
+  if negated {
+    regex.push('^');
+  }
+  for &(low, high) in ranges {
+    push_char(regex, low);
+    if high > low {
+      regex.push('-');
+      push_char(regex, high);
+    }
+  }
+  regex.push(']');
+}
+
+/// Returns the offsets of the extglob groups of `glob` and the spans of its sequence
+/// braces, in the order the parser reads them.
+fn locate(glob: &[u8], options: &MatchOptions) -> (Vec<usize>, Vec<Range<usize>>) {
+  let mut groups = Vec::new();
+  let mut sequences = Vec::new();
+
+  // The length of the possibly escaped byte at `index`.
+  let byte_len = |index: usize| 1 + (options.backslash_escape && glob[index] == b'\\') as usize;
+
+  let mut index = 0;
+  while index < glob.len() {
+    match glob[index] {
+      b'@' | b'?' | b'*' | b'+' | b'!' if options.extglob && glob.get(index + 1) == Some(&b'(') => {
+        groups.push(index);
+        index += 2;
+      }
+      b'[' => {
+        index += 1;
+        if matches!(glob.get(index), Some(b'^' | b'!')) {
+          index += 1;
+        }
+
+        let mut first = true;
+        while index < glob.len() && (first || glob[index] != b']') {
+          first = false;
+          if glob[index..].starts_with(b"[:") {
+            if let Some(Ok((_, end))) = parse_posix_class(glob, index, true) {
+              index = end;
+              continue;
+            }
+          }
+
+          index += byte_len(index);
+          if index + 1 < glob.len() && glob[index] == b'-' && glob[index + 1] != b']' {
+            index += 1 + byte_len(index + 1);
+          }
+        }
+        index += 1;
+      }
+      b'{' => match parse_sequence(glob, index, options) {
+        Some((_, end)) => {
+          sequences.push(index..end);
+          index = end;
+        }
+        None => index += 1,
+      },
+      _ => index += byte_len(index),
+    }
+  }
+
+  (groups, sequences)
+}
@@ -0,0 +1,223 @@
+use std::cell::Cell;
+
+use crate::{engine, glob::Token, Glob, GlobError, MatchOptions, Separator};
+
+/// A set of glob patterns matched against a path together.
+///
+/// Patterns are indexed by the literal text they start with, so a prefix shared by several
+/// patterns, such as `src/` in `src/**/*.ts` and `src/*.rs`, is compared once per path and
+/// patterns whose prefix doesn't match are never run.
+///
+/// ```rust
+/// use fast_glob::GlobSet;
+///
+/// let set = GlobSet::new(["src/**/*.ts", "src/*.rs", "**/*.md"]).unwrap();
+///
+/// assert_eq!(set.matches("src/lib.rs"), vec![1]);
+/// assert_eq!(set.first_match("src/docs/README.md"), Some(2));
+/// assert!(!set.is_match("tests/test.rs"));
+/// ```
+#[derive(Clone, Debug)]
+pub struct GlobSet {
+  globs: Vec<Glob>,
+  /// A trie over the literal prefixes of the globs, rooted at `nodes[0]`.
+  nodes: Vec<Node>,
+  /// Globs left out of the trie: negated globs, which match whenever their prefix doesn't,
+  /// and basename globs, whose prefix can start at any path segment.
+  unindexed: Vec<usize>,
+}
+
+#[derive(Clone, Debug)]
+struct Node {
+  token: Token,
+  /// The separators the token matches if it is a [`Token::Separator`].
+  separators: [u8; 2],
+  children: Vec<usize>,
+  /// Globs whose literal prefix ends at this node.
+  globs: Vec<usize>,
+}
+
+impl GlobSet {
+  /// Compiles `globs` into a set. Patterns are identified by their position in `globs`.
+  ///
+  /// Returns the error of the first malformed pattern. To find out which pattern it was,
+  /// compile each one with [`Glob::new`] and collect them into a set instead.
+  pub fn new<I>(globs: I) -> Result<GlobSet, GlobError>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+  {
+    GlobSet::with_options(globs, MatchOptions::default())
+  }
+
+  /// Compiles `globs` into a set, with the given options applied to every pattern.
+  pub fn with_options<I>(globs: I, options: MatchOptions) -> Result<GlobSet, GlobError>
+  where
+    I: IntoIterator,
+    I::Item: AsRef<[u8]>,
+  {
+    globs
+      .into_iter()
+      .map(|glob| Glob::with_options(glob, options))
+      .collect()
+  }
+
+  fn push(&mut self, glob: Glob) {
+    let index = self.globs.len();
+
+    if glob.negated || glob.basename {
+      self.unindexed.push(index);
+      self.globs.push(glob);
+      return;
+    }
+
+    let separators = glob.options.separator.bytes();
+    let mut node = 0;
+    for &token in glob.tokens.iter() {
+      if !matches!(
+        token,
+        Token::Byte(_) | Token::Caseless(_) | Token::Separator
+      ) {
+        break;
+      }
+
+      node = match self.nodes[node].children.iter().find(|&&child| {
+        self.nodes[child].token == token && self.nodes[child].separators == separators
+      }) {
+        Some(&child) => child,
+        None => {
+          let child = self.nodes.len();
+          self.nodes.push(Node {
+            token,
+            separators,
+            children: Vec::new(),
+            globs: Vec::new(),
+          });
+          self.nodes[node].children.push(child);
+          child
+        }
+      };
+    }
+
+    self.nodes[node].globs.push(index);
+    self.globs.push(glob);
+  }
+
+  /// Returns the number of patterns in this set.
+  pub fn len(&self) -> usize {
+    self.globs.len()
+  }
+
+  /// Returns whether this set contains no patterns.
+  pub fn is_empty(&self) -> bool {
+    self.globs.is_empty()
+  }
+
+  /// Returns whether any pattern in this set matches `path`.
+  pub fn is_match(&self, path: impl AsRef<[u8]>) -> bool {
+    self.search(path.as_ref(), &mut |_| true, &mut |_| false)
+  }
+
+  /// Returns the indices of all patterns matching `path`, in ascending order.
+  pub fn matches(&self, path: impl AsRef<[u8]>) -> Vec<usize> {
+    let mut matches = Vec::new();
+    self.search(path.as_ref(), &mut |_| true, &mut |index| {
+      matches.push(index);
+      true
+    });
+
+    matches.sort_unstable();
+    matches
+  }
+
+  /// Returns the index of the first pattern matching `path`.
+  pub fn first_match(&self, path: impl AsRef<[u8]>) -> Option<usize> {
+    let first = Cell::new(usize::MAX);
+    self.search(
+      path.as_ref(),
+      &mut |index| index < first.get(),
+      &mut |index| {
+        first.set(index);
+        true
+      },
+    );
+
+    (first.get() != usize::MAX).then(|| first.get())
+  }
+
+  /// Runs every glob accepted by `is_candidate` against `path`, calling `on_match` for
+  /// each one that matches until it returns `false`. Returns whether the search stopped
+  /// early.
+  fn search(
+    &self,
+    path: &[u8],
+    is_candidate: &mut dyn FnMut(usize) -> bool,
+    on_match: &mut dyn FnMut(usize) -> bool,
+  ) -> bool {
+    if self.search_node(0, path, 0, is_candidate, on_match) {
+      return true;
+    }
+
+    for &index in &self.unindexed {
+      if is_candidate(index) && self.globs[index].is_match(path) && !on_match(index) {
+        return true;
+      }
+    }
+
+    false
+  }
+
+  fn search_node(
+    &self,
+    node: usize,
+    path: &[u8],
+    path_index: usize,
+    is_candidate: &mut dyn FnMut(usize) -> bool,
+    on_match: &mut dyn FnMut(usize) -> bool,
+  ) -> bool {
+    let node = &self.nodes[node];
+
+    for &index in &node.globs {
+      if is_candidate(index)
+        && engine::match_glob_from(&self.globs[index], path, path_index, path_index)
+        && !on_match(index)
+      {
+        return true;
+      }
+    }
+
+    if path_index < path.len() {
+      for &child in &node.children {
+        let child_node = &self.nodes[child];
+        if engine::match_literal(child_node.token, path[path_index], child_node.separators)
+          && self.search_node(child, path, path_index + 1, is_candidate, on_match)
+        {
+          return true;
+        }
+      }
+    }
+
+    false
+  }
+}
+
+impl FromIterator<Glob> for GlobSet {
+  fn from_iter<I: IntoIterator<Item = Glob>>(globs: I) -> GlobSet {
+    let mut set = GlobSet {
+      globs: Vec::new(),
+      nodes: vec![Node {
+        token: Token::Invalid,
+        separators: Separator::default().bytes(),
+        children: Vec::new(),
+        globs: Vec::new(),
+      }],
+      unindexed: Vec::new(),
+    };
+
+    for glob in globs {
+      set.push(glob);
+    }
+
+    set
+  }
+}
@@ -0,0 +1,882 @@
+use std::{
+  borrow::Cow,
+  collections::VecDeque,
+  ffi::{OsStr, OsString},
+  fs::{self, DirEntry, FileType, ReadDir},
+  io,
+  num::NonZeroUsize,
+  path::{Component, Path, PathBuf},
+  sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    mpsc, Arc, Condvar, Mutex,
+  },
+  thread, vec,
+};
+
+use crate::{
+  gitignore::{Gitignore, Match},
+  DescendantMatch, Glob, GlobError, MatchOptions,
+};
+
+/// Walks the directory tree under `root`, yielding the files and directories whose path
+/// relative to `root` matches any of `patterns`.
+///
+/// Only the directories that can contain a match are read: each pattern is walked from
+/// its static base, the literal directory it starts with such as `src` for `src/**/*.rs`,
+/// and subtrees no pattern can match are skipped with [`Glob::could_match_descendant`].
+///
+/// Entries are yielded depth first, each directory before its contents, in the order the
+/// file system lists them. Symbolic links are yielded like other entries but never
+/// followed. A static base that doesn't exist yields nothing, while other I/O errors are
+/// yielded as they happen and the walk carries on. A malformed pattern is yielded as an
+/// [`io::ErrorKind::InvalidInput`] error wrapping its [`GlobError`], and nothing else.
+/// So is a pattern whose static base is absolute or climbs out of `root` with `..`, such
+/// as `/etc/host*` or `../src/**`, as the walk never leaves `root`. A `..` that stays
+/// inside `root` is resolved, so `src/../tests/*` walks `tests`.
+///
+/// ```rust,no_run
+/// use fast_glob::walk;
+///
+/// for entry in walk(".", ["src/**/*.rs", "Cargo.toml"]) {
+///   println!("{}", entry.unwrap().path().display());
+/// }
+/// ```
+pub fn walk<I>(root: impl AsRef<Path>, patterns: I) -> Walk
+where
+  I: IntoIterator,
+  I::Item: AsRef<[u8]>,
+{
+  walk_with_options(root, patterns, MatchOptions::default())
+}
+
+/// Walks the directory tree under `root` like [`walk`], with the given options applied
+/// to every pattern.
+pub fn walk_with_options<I>(root: impl AsRef<Path>, patterns: I, options: MatchOptions) -> Walk
+where
+  I: IntoIterator,
+  I::Item: AsRef<[u8]>,
+{
+  let root = root.as_ref().to_path_buf();
+  match compile(patterns, options) {
+    Ok(globs) => {
+      let mut bases = static_bases(&globs);
+      bases.reverse();
+      Walk {
+        root,
+        globs,
+        bases,
+        stack: Vec::new(),
+        ignore_files: IgnoreFiles::default(),
+        error: None,
+      }
+    }
+    Err(err) => Walk {
+      root,
+      globs: Vec::new(),
+      bases: Vec::new(),
+      stack: Vec::new(),
+      ignore_files: IgnoreFiles::default(),
+      error: Some(err),
+    },
+  }
+}
+
+/// Walks the directory tree under `root` on several threads, yielding the same entries
+/// as [`walk`].
+///
+/// Directories are read by a pool of threads, each taking work from the others once it
+/// runs out of its own. By default entries are yielded as soon as they are found, in no
+/// particular order; see [`WalkParallel::sorted`] for a deterministic order.
+///
+/// ```rust,no_run
+/// use fast_glob::walk_parallel;
+///
+/// for entry in walk_parallel(".", ["src/**/*.rs"]).threads(4).sorted(true) {
+///   println!("{}", entry.unwrap().path().display());
+/// }
+/// ```
+pub fn walk_parallel<I>(root: impl AsRef<Path>, patterns: I) -> WalkParallel
+where
+  I: IntoIterator,
+  I::Item: AsRef<[u8]>,
+{
+  walk_parallel_with_options(root, patterns, MatchOptions::default())
+}
+
+/// Walks the directory tree under `root` on several threads like [`walk_parallel`], with
+/// the given options applied to every pattern.
+pub fn walk_parallel_with_options<I>(
+  root: impl AsRef<Path>,
+  patterns: I,
+  options: MatchOptions,
+) -> WalkParallel
+where
+  I: IntoIterator,
+  I::Item: AsRef<[u8]>,
+{
+  WalkParallel {
+    root: root.as_ref().to_path_buf(),
+    globs: compile(patterns, options),
+    threads: thread::available_parallelism().map_or(1, NonZeroUsize::get),
+    sorted: false,
+    ignore_files: IgnoreFiles::default(),
+  }
+}
+
+fn compile<I>(patterns: I, options: MatchOptions) -> io::Result<Vec<Glob>>
+where
+  I: IntoIterator,
+  I::Item: AsRef<[u8]>,
+{
+  let patterns: Vec<I::Item> = patterns.into_iter().collect();
+  let globs = patterns
+    .iter()
+    .map(|pattern| Glob::with_options(pattern, options))
+    .collect::<Result<Vec<_>, GlobError>>()
+    .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+  globs
+    .into_iter()
+    .zip(&patterns)
+    .map(|(glob, pattern)| normalize_base(glob, pattern.as_ref()))
+    .collect()
+}
+
+/// Splits a static base into the names of its segments, resolving `.` and `..`, or
+/// returns `None` if it is absolute or climbs out of the directory it is relative to.
+fn base_names(base: &[u8]) -> Option<Vec<&[u8]>> {
+  // Matching the components catches absolute paths of every platform, such as `C:/`.
+  let lossy = String::from_utf8_lossy(base);
+  let absolute = Path::new(&*lossy)
+    .components()
+    .any(|component| matches!(component, Component::RootDir | Component::Prefix(_)));
+  if absolute {
+    return None;
+  }
+
+  let mut names = Vec::new();
+  for name in base.split(|&c| c == b'/') {
+    match name {
+      b"" | b"." => {}
+      b".." => {
+        names.pop()?;
+      }
+      name => names.push(name),
+    }
+  }
+  Some(names)
+}
+
+/// Recompiles `glob` with its static base spelled without empty, `.` or `..` segments, the
+/// way the paths of the walked entries are, so that `./src/*.rs` and `src/../src/*.rs`
+/// match `src/lib.rs`.
+///
+/// Fails if the base leads out of the walked directory, or if it has a `..` segment but the
+/// pattern doesn't start with the base verbatim, as with an escape, so it can't be
+/// rewritten.
+fn normalize_base(glob: Glob, pattern: &[u8]) -> io::Result<Glob> {
+  let base = glob.static_base();
+  let error = |message| {
+    let base = String::from_utf8_lossy(&base);
+    io::Error::new(
+      io::ErrorKind::InvalidInput,
+      format!("pattern base `{base}` {message}"),
+    )
+  };
+
+  // Static bases are looked up under the root, so they must not lead out of it.
+  let names = base_names(&base).ok_or_else(|| error("is outside the walked directory"))?;
+  if names.len() == base.split(|&c| c == b'/').count() {
+    return Ok(glob);
+  }
+
+  let rest = match pattern.strip_prefix(&base[..]) {
+    Some(rest) if rest.first() == Some(&b'/') => rest,
+    _ if base.split(|&c| c == b'/').any(|name| name == b"..") => {
+      return Err(error("has a `..` that can't be resolved"));
+    }
+    // Matched as they are, the paths of the entries just have to be spelled the same.
+    _ => return Ok(glob),
+  };
+
+  let mut normalized = names.join(&b'/');
+  let rest = &rest[rest.iter().take_while(|&&c| c == b'/').count()..];
+  if !normalized.is_empty() {
+    normalized.push(b'/');
+  }
+  normalized.extend_from_slice(rest);
+
+  match Glob::with_options(&normalized, glob.options) {
+    Ok(normalized) if normalized.basename == glob.basename => Ok(normalized),
+    _ => Ok(glob),
+  }
+}
+
+/// Returns the static bases of `globs` to walk, leaving out those inside another one, in
+/// ascending order of their path segments.
+fn static_bases(globs: &[Glob]) -> Vec<Vec<u8>> {
+  let mut bases: Vec<Vec<&[u8]>> = Vec::new();
+  let static_bases: Vec<Vec<u8>> = globs.iter().map(Glob::static_base).collect();
+  for base in &static_bases {
+    // The base becomes a path, which can only be built from valid UTF-8 everywhere.
+    let names = match std::str::from_utf8(base) {
+      Ok(_) => base_names(base).unwrap_or_default(),
+      Err(_) => Vec::new(),
+    };
+    bases.push(names);
+  }
+
+  // Sorted by segments, each base comes right before those inside it, which are walked
+  // along with it.
+  bases.sort_unstable();
+  bases.dedup_by(|base, parent| base.starts_with(parent));
+  bases.iter().map(|names| names.join(&b'/')).collect()
+}
+
+/// A file or directory found by [`walk`].
+#[derive(Clone, Debug)]
+pub struct Entry {
+  path: PathBuf,
+  file_type: FileType,
+}
+
+impl Entry {
+  /// Returns the path of this entry, relative to the root of the walk.
+  pub fn path(&self) -> &Path {
+    &self.path
+  }
+
+  /// Returns the path of this entry, relative to the root of the walk.
+  pub fn into_path(self) -> PathBuf {
+    self.path
+  }
+
+  /// Returns the type of this entry. Symbolic links aren't followed.
+  pub fn file_type(&self) -> FileType {
+    self.file_type
+  }
+
+  /// Returns whether this entry is a directory.
+  pub fn is_dir(&self) -> bool {
+    self.file_type.is_dir()
+  }
+}
+
+/// An entry if it matches, along with the directory to read if it is one whose
+/// descendants can match.
+type Visited = (Option<Entry>, Option<DirPath>);
+
+/// The ignore files read while walking.
+#[derive(Clone, Debug, Default)]
+struct IgnoreFiles {
+  /// Whether `.gitignore` files are read, along with `.git/info/exclude`.
+  git_ignore: bool,
+  /// Whether `.ignore` files are read.
+  dot_ignore: bool,
+  /// The names of other ignore files to read.
+  custom: Vec<OsString>,
+}
+
+impl IgnoreFiles {
+  /// Returns whether entries named `name` are skipped: `.git` directories hold no files of
+  /// the working tree whose `.gitignore` files are honored.
+  fn skips(&self, name: &[u8]) -> bool {
+    self.git_ignore && name == b".git"
+  }
+
+  fn add_custom(&mut self, name: OsString) {
+    if !self.custom.contains(&name) {
+      self.custom.push(name);
+    }
+  }
+
+  /// Reads the ignore files of `dir`, adding their rules to it.
+  fn load(&self, root: &Path, dir: &mut DirPath) {
+    if !self.git_ignore && !self.dot_ignore && self.custom.is_empty() {
+      return;
+    }
+
+    let read = |path: PathBuf| {
+      let contents = fs::read_to_string(path).ok()?;
+      Some(Gitignore::new(contents.lines()))
+    };
+
+    // Later files take precedence.
+    let path = root.join(&dir.path);
+    let mut rules = Vec::new();
+    if self.git_ignore {
+      if path.join(".git").exists() {
+        rules.extend(read(path.join(".git/info/exclude")));
+      }
+      rules.extend(read(path.join(".gitignore")));
+    }
+    if self.dot_ignore {
+      rules.extend(read(path.join(".ignore")));
+    }
+    rules.extend(self.custom.iter().filter_map(|name| read(path.join(name))));
+
+    if !rules.is_empty() {
+      dir.ignore = Some(Arc::new(IgnoreLayer {
+        parent: dir.ignore.take(),
+        dir: dir.glob_path.clone(),
+        rules,
+      }));
+    }
+  }
+}
+
+/// The rules of the ignore files in a directory, on top of those of its parents.
+#[derive(Debug)]
+struct IgnoreLayer {
+  parent: Option<Arc<IgnoreLayer>>,
+  /// The path of the directory matched against the patterns, ending with a separator
+  /// unless it is empty.
+  dir: Vec<u8>,
+  /// The rules of each ignore file, from the lowest precedence to the highest.
+  rules: Vec<Gitignore>,
+}
+
+/// A directory to read.
+#[derive(Debug)]
+struct DirPath {
+  /// The path of the directory relative to the root.
+  path: PathBuf,
+  /// The path matched against the patterns, ending with a separator unless it is empty.
+  glob_path: Vec<u8>,
+  /// The separator joining the segments of `glob_path`, the first one the patterns match.
+  separator: u8,
+  /// The rules of the ignore files read so far, innermost first.
+  ignore: Option<Arc<IgnoreLayer>>,
+}
+
+impl DirPath {
+  fn new(
+    path: PathBuf,
+    mut glob_path: Vec<u8>,
+    separator: u8,
+    ignore: Option<Arc<IgnoreLayer>>,
+  ) -> DirPath {
+    if !glob_path.is_empty() {
+      glob_path.push(separator);
+    }
+    DirPath {
+      path,
+      glob_path,
+      separator,
+      ignore,
+    }
+  }
+
+  /// Returns whether the ignore files read so far ignore the entry at `glob_path`. Rules
+  /// in deeper directories take precedence, as do later ignore files in a directory.
+  fn is_ignored(&self, glob_path: &[u8], is_dir: bool) -> bool {
+    if self.ignore.is_none() {
+      return false;
+    }
+
+    // Ignore files always separate segments with a `/`.
+    let glob_path: Cow<[u8]> = match self.separator {
+      b'/' => Cow::Borrowed(glob_path),
+      separator => Cow::Owned(
+        glob_path
+          .iter()
+          .map(|&c| if c == separator { b'/' } else { c })
+          .collect(),
+      ),
+    };
+
+    let mut layer = self.ignore.as_deref();
+    while let Some(IgnoreLayer { parent, dir, rules }) = layer {
+      for rules in rules.iter().rev() {
+        match rules.matched(&glob_path[dir.len()..], is_dir) {
+          Match::Ignore => return true,
+          Match::Whitelist => return false,
+          Match::None => {}
+        }
+      }
+      layer = parent.as_deref();
+    }
+
+    false
+  }
+
+  /// Returns whether any of the descendants of this directory can match `globs`.
+  fn could_match_descendant(&self, globs: &[Glob]) -> bool {
+    globs
+      .iter()
+      .any(|glob| glob.could_match_descendant(&self.glob_path) != DescendantMatch::No)
+  }
+
+  /// Returns the entry `entry` of this directory if it matches `globs`, along with the
+  /// directory to read next if it is one whose descendants can match.
+  fn visit(
+    &self,
+    globs: &[Glob],
+    ignore_files: &IgnoreFiles,
+    entry: DirEntry,
+  ) -> io::Result<Visited> {
+    let name = entry.file_name();
+    if ignore_files.skips(OsStr::as_encoded_bytes(&name)) {
+      return Ok((None, None));
+    }
+
+    let file_type = entry.file_type()?;
+    let path = self.path.join(&name);
+    let mut glob_path = self.glob_path.clone();
+    glob_path.extend_from_slice(OsStr::as_encoded_bytes(&name));
+
+    if self.is_ignored(&glob_path, file_type.is_dir()) {
+      return Ok((None, None));
+    }
+
+    let is_match = globs.iter().any(|glob| glob.is_match(&glob_path));
+    let dir = file_type
+      .is_dir()
+      .then(|| DirPath::new(path.clone(), glob_path, self.separator, self.ignore.clone()))
+      .filter(|dir| dir.could_match_descendant(globs));
+
+    Ok((is_match.then_some(Entry { path, file_type }), dir))
+  }
+}
+
+/// Looks up the static base `base` under `root`, returning it if it matches `globs`,
+/// along with the directory to read if its descendants can match.
+fn visit_base(
+  root: &Path,
+  globs: &[Glob],
+  ignore_files: &IgnoreFiles,
+  base: Vec<u8>,
+) -> io::Result<Visited> {
+  let path = PathBuf::from(String::from_utf8(base.clone()).unwrap_or_default());
+  let separator = globs
+    .first()
+    .map_or(b'/', |glob| glob.options.separator.bytes()[0]);
+  if base.is_empty() {
+    let dir = DirPath::new(path, base, separator, None);
+    return Ok((None, dir.could_match_descendant(globs).then_some(dir)));
+  }
+
+  if base
+    .split(|&c| c == b'/')
+    .any(|name| ignore_files.skips(name))
+  {
+    return Ok((None, None));
+  }
+
+  let file_type = match fs::metadata(root.join(&path)) {
+    Ok(metadata) => metadata.file_type(),
+    Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok((None, None)),
+    Err(err) => return Err(err),
+  };
+  if !file_type.is_dir() {
+    return Ok((None, None));
+  }
+
+  // Static bases are joined with `/`, unlike the paths matched against the patterns.
+  let glob_base: Vec<u8> = base
+    .iter()
+    .map(|&c| if c == b'/' { separator } else { c })
+    .collect();
+
+  // The base is skipped if a parent is ignored, and inherits the ignore files of them all.
+  let mut parent = DirPath::new(PathBuf::new(), Vec::new(), separator, None);
+  for (index, c) in base.iter().copied().enumerate().chain([(base.len(), b'/')]) {
+    if c != b'/' || index == 0 {
+      continue;
+    }
+
+    ignore_files.load(root, &mut parent);
+    if parent.is_ignored(&glob_base[..index], true) {
+      return Ok((None, None));
+    }
+
+    let prefix = String::from_utf8_lossy(&base[..index]).into_owned();
+    parent = DirPath::new(
+      prefix.into(),
+      glob_base[..index].to_vec(),
+      separator,
+      parent.ignore,
+    );
+  }
+
+  let is_match = globs.iter().any(|glob| glob.is_match(&glob_base));
+  let dir = DirPath::new(path.clone(), glob_base, separator, parent.ignore);
+  Ok((
+    is_match.then_some(Entry { path, file_type }),
+    dir.could_match_descendant(globs).then_some(dir),
+  ))
+}
+
+/// An iterator over the entries matching a set of patterns, created by [`walk`].
+#[derive(Debug)]
+pub struct Walk {
+  root: PathBuf,
+  globs: Vec<Glob>,
+  /// The static bases still to walk, in reverse order.
+  bases: Vec<Vec<u8>>,
+  /// The directories being read, innermost last.
+  stack: Vec<(ReadDir, DirPath)>,
+  ignore_files: IgnoreFiles,
+  /// An error to yield before anything else.
+  error: Option<io::Error>,
+}
+
+impl Walk {
+  /// Whether the `.gitignore` files found along the way are honored, along with the
+  /// `.git/info/exclude` file of any directory containing a `.git`.
+  ///
+  /// An ignore file applies to the directory it is in and everything below it, with the
+  /// rules of deeper directories taking precedence. Ignored entries are skipped even if a
+  /// pattern matches them, and ignored directories aren't read. The `.git` directories
+  /// themselves are skipped too. Ignore files above `root` aren't read, and those that
+  /// can't be read are skipped.
+  ///
+  /// Defaults to `false`. Must be set before the walk starts.
+  ///
+  /// ```rust,no_run
+  /// use fast_glob::walk;
+  ///
+  /// for entry in walk(".", ["**/*.rs"]).git_ignore(true) {
+  ///   println!("{}", entry.unwrap().path().display());
+  /// }
+  /// ```
+  pub fn git_ignore(mut self, yes: bool) -> Walk {
+    self.ignore_files.git_ignore = yes;
+    self
+  }
+
+  /// Whether the `.ignore` files found along the way are honored like `.gitignore` files,
+  /// taking precedence over them.
+  ///
+  /// Defaults to `false`. Must be set before the walk starts.
+  pub fn dot_ignore(mut self, yes: bool) -> Walk {
+    self.ignore_files.dot_ignore = yes;
+    self
+  }
+
+  /// Honors the ignore files named `name` like `.gitignore` files, taking precedence over
+  /// the other ignore files, and over those added before.
+  ///
+  /// Must be set before the walk starts.
+  pub fn custom_ignore_file(mut self, name: impl Into<OsString>) -> Walk {
+    self.ignore_files.add_custom(name.into());
+    self
+  }
+
+  /// Starts reading `dir`, or returns why it can't be read.
+  fn push_dir(&mut self, dir: Option<DirPath>) -> io::Result<()> {
+    if let Some(mut dir) = dir {
+      self.ignore_files.load(&self.root, &mut dir);
+      let entries = fs::read_dir(self.root.join(&dir.path))?;
+      self.stack.push((entries, dir));
+    }
+    Ok(())
+  }
+}
+
+impl Iterator for Walk {
+  type Item = io::Result<Entry>;
+
+  fn next(&mut self) -> Option<io::Result<Entry>> {
+    if let Some(err) = self.error.take() {
+      return Some(Err(err));
+    }
+
+    loop {
+      let visited = match self.stack.last_mut() {
+        Some((entries, dir)) => match entries.next() {
+          Some(Ok(entry)) => dir.visit(&self.globs, &self.ignore_files, entry),
+          Some(Err(err)) => Err(err),
+          None => {
+            self.stack.pop();
+            continue;
+          }
+        },
+        None => {
+          let base = self.bases.pop()?;
+          visit_base(&self.root, &self.globs, &self.ignore_files, base)
+        }
+      };
+
+      let (entry, dir) = match visited {
+        Ok(visited) => visited,
+        Err(err) => return Some(Err(err)),
+      };
+      if let Err(err) = self.push_dir(dir) {
+        self.error = Some(err);
+      }
+
+      if let Some(entry) = entry {
+        return Some(Ok(entry));
+      }
+      if let Some(err) = self.error.take() {
+        return Some(Err(err));
+      }
+    }
+  }
+}
+
+/// A parallel walk over the entries matching a set of patterns, created by
+/// [`walk_parallel`].
+///
+/// Iterating over it starts the walk.
+#[derive(Debug)]
+pub struct WalkParallel {
+  root: PathBuf,
+  globs: io::Result<Vec<Glob>>,
+  threads: usize,
+  sorted: bool,
+  ignore_files: IgnoreFiles,
+}
+
+impl WalkParallel {
+  /// The number of threads reading directories.
+  ///
+  /// Defaults to the parallelism available to the process, see
+  /// [`std::thread::available_parallelism`].
+  pub fn threads(mut self, threads: usize) -> WalkParallel {
+    self.threads = threads.max(1);
+    self
+  }
+
+  /// Whether entries are yielded in the order of their paths, such as `a`, `a/b`, `a/c`,
+  /// `b`, rather than as soon as they are found. The whole tree is then walked before the
+  /// first entry is yielded. Errors follow the entries.
+  ///
+  /// Defaults to `false`.
+  pub fn sorted(mut self, yes: bool) -> WalkParallel {
+    self.sorted = yes;
+    self
+  }
+
+  /// Whether `.gitignore` files are honored, see [`Walk::git_ignore`].
+  ///
+  /// Defaults to `false`.
+  pub fn git_ignore(mut self, yes: bool) -> WalkParallel {
+    self.ignore_files.git_ignore = yes;
+    self
+  }
+
+  /// Whether `.ignore` files are honored, see [`Walk::dot_ignore`].
+  ///
+  /// Defaults to `false`.
+  pub fn dot_ignore(mut self, yes: bool) -> WalkParallel {
+    self.ignore_files.dot_ignore = yes;
+    self
+  }
+
+  /// Honors the ignore files named `name`, see [`Walk::custom_ignore_file`].
+  pub fn custom_ignore_file(mut self, name: impl Into<OsString>) -> WalkParallel {
+    self.ignore_files.add_custom(name.into());
+    self
+  }
+}
+
+impl IntoIterator for WalkParallel {
+  type Item = io::Result<Entry>;
+  type IntoIter = WalkParallelIter;
+
+  fn into_iter(self) -> WalkParallelIter {
+    let globs = match self.globs {
+      Ok(globs) => globs,
+      Err(err) => {
+        return WalkParallelIter {
+          inner: Inner::Sorted(vec![Err(err)].into_iter()),
+        }
+      }
+    };
+
+    let (sender, receiver) = mpsc::channel();
+    let pool = Arc::new(Pool {
+      bases: static_bases(&globs),
+      root: self.root,
+      globs,
+      ignore_files: self.ignore_files,
+      queues: (0..self.threads).map(|_| Mutex::default()).collect(),
+      pending: AtomicUsize::new(1),
+      stopped: AtomicBool::new(false),
+      idle: Mutex::new(()),
+      wake: Condvar::new(),
+    });
+
+    for index in 0..self.threads {
+      let pool = Arc::clone(&pool);
+      let sender = sender.clone();
+      thread::spawn(move || pool.work(index, &sender));
+    }
+    drop(sender);
+
+    if !self.sorted {
+      return WalkParallelIter {
+        inner: Inner::Streaming(receiver.into_iter()),
+      };
+    }
+
+    let mut entries = Vec::new();
+    let mut errors = Vec::new();
+    for result in receiver {
+      match result {
+        Ok(entry) => entries.push(entry),
+        Err(err) => errors.push(Err(err)),
+      }
+    }
+
+    // Paths are compared by component, which puts each directory right before its
+    // contents.
+    entries.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+    let results: Vec<_> = entries.into_iter().map(Ok).chain(errors).collect();
+    WalkParallelIter {
+      inner: Inner::Sorted(results.into_iter()),
+    }
+  }
+}
+
+/// An iterator over the entries found by a [`WalkParallel`].
+#[derive(Debug)]
+pub struct WalkParallelIter {
+  inner: Inner,
+}
+
+#[derive(Debug)]
+enum Inner {
+  Streaming(mpsc::IntoIter<io::Result<Entry>>),
+  Sorted(vec::IntoIter<io::Result<Entry>>),
+}
+
+impl Iterator for WalkParallelIter {
+  type Item = io::Result<Entry>;
+
+  fn next(&mut self) -> Option<io::Result<Entry>> {
+    match &mut self.inner {
+      Inner::Streaming(results) => results.next(),
+      Inner::Sorted(results) => results.next(),
+    }
+  }
+}
+
+/// The state shared by the threads of a parallel walk.
+struct Pool {
+  root: PathBuf,
+  globs: Vec<Glob>,
+  ignore_files: IgnoreFiles,
+  /// The static bases, looked up by the first thread before anything else.
+  bases: Vec<Vec<u8>>,
+  /// The directories waiting to be read, queued by each thread.
+  queues: Vec<Mutex<VecDeque<DirPath>>>,
+  /// The number of directories queued or being read, plus one until the static bases are
+  /// looked up.
+  pending: AtomicUsize,
+  /// Set once the results are no longer received.
+  stopped: AtomicBool,
+  /// Held by an idle thread while it looks for work, until it waits on `wake`.
+  idle: Mutex<()>,
+  /// Notified when a directory is queued, or when the walk is over.
+  wake: Condvar,
+}
+
+type Sender = mpsc::Sender<io::Result<Entry>>;
+
+impl Pool {
+  /// Reads directories on thread `index` until there are none left.
+  fn work(&self, index: usize, sender: &Sender) {
+    if index == 0 {
+      for base in &self.bases {
+        let visited = visit_base(&self.root, &self.globs, &self.ignore_files, base.clone());
+        self.send(index, visited, sender);
+      }
+      self.finish();
+    }
+
+    while let Some(mut dir) = self.next(index) {
+      self.ignore_files.load(&self.root, &mut dir);
+      match fs::read_dir(self.root.join(&dir.path)) {
+        Ok(entries) => {
+          for entry in entries {
+            let visited = entry.and_then(|entry| dir.visit(&self.globs, &self.ignore_files, entry));
+            self.send(index, visited, sender);
+          }
+        }
+        Err(err) => self.send(index, Err(err), sender),
+      }
+      self.finish();
+    }
+  }
+
+  /// Takes the next directory for thread `index` to read, waiting for one to be queued
+  /// while others are still being read. Returns `None` once the walk is over.
+  fn next(&self, index: usize) -> Option<DirPath> {
+    if self.stopped.load(Ordering::SeqCst) {
+      return None;
+    }
+    if let Some(dir) = self.pop(index) {
+      return Some(dir);
+    }
+
+    // Queuing a directory or finishing the walk takes the lock before notifying, so it
+    // can't happen between the checks and the wait.
+    let mut idle = self.idle.lock().unwrap();
+    loop {
+      if self.stopped.load(Ordering::SeqCst) {
+        return None;
+      }
+      if let Some(dir) = self.pop(index) {
+        return Some(dir);
+      }
+      if self.pending.load(Ordering::SeqCst) == 0 {
+        return None;
+      }
+      idle = self.wake.wait(idle).unwrap();
+    }
+  }
+
+  /// Marks a directory, or the static bases, as done.
+  fn finish(&self) {
+    if self.pending.fetch_sub(1, Ordering::SeqCst) == 1 {
+      self.notify_all();
+    }
+  }
+
+  /// Wakes every idle thread.
+  fn notify_all(&self) {
+    drop(self.idle.lock().unwrap());
+    self.wake.notify_all();
+  }
+
+  /// Queues the directory of a visited entry on thread `index` and sends the entry.
+  fn send(&self, index: usize, visited: io::Result<Visited>, sender: &Sender) {
+    let result = match visited {
+      Ok((entry, dir)) => {
+        if let Some(dir) = dir {
+          self.pending.fetch_add(1, Ordering::SeqCst);
+          self.queues[index].lock().unwrap().push_back(dir);
+          drop(self.idle.lock().unwrap());
+          self.wake.notify_one();
+        }
+        entry.map(Ok)
+      }
+      Err(err) => Some(Err(err)),
+    };
+
+    if let Some(result) = result {
+      if sender.send(result).is_err() && !self.stopped.swap(true, Ordering::SeqCst) {
+        self.notify_all();
+      }
+    }
+  }
+
+  /// Takes the next directory for thread `index` to read: the last one it queued, or
+  /// else the first one queued by another thread.
+  fn pop(&self, index: usize) -> Option<DirPath> {
+    if let Some(dir) = self.queues[index].lock().unwrap().pop_back() {
+      return Some(dir);
+    }
+
+    let len = self.queues.len();
+    (1..len).find_map(|offset| {
+      self.queues[(index + offset) % len]
+        .lock()
+        .unwrap()
+        .pop_front()
+    })
+  }
+}
@@ -2,9 +2,15 @@ use fast_glob::*;
 
 #[cfg(test)]
 mod tests {
+  use std::time::{Duration, Instant};
+
   use super::*;
 
   #[test]
+  #[allow(
+    clippy::unnecessary_to_owned,
+    clippy::needless_borrows_for_generic_args
+  )]
   fn generic_input() {
     assert!(glob_match("**/*", "foo"));
     assert!(glob_match("**/*".to_string(), "foo"));
@@ -745,6 +751,64 @@ mod tests {
     assert!(glob_match("[^a-c]*", "BewAre"));
   }
 
+  #[test]
+  fn posix_classes() {
+    assert!(glob_match("[[:alpha:]]", "a"));
+    assert!(glob_match("[[:alpha:]]", "Z"));
+    assert!(!glob_match("[[:alpha:]]", "1"));
+    assert!(glob_match("[[:digit:]]*", "1abc"));
+    assert!(!glob_match("[[:digit:]]*", "abc"));
+    assert!(glob_match("[[:upper:]]*", "Beware"));
+    assert!(!glob_match("[[:upper:]]*", "beware"));
+    assert!(glob_match("[[:lower:]]*", "beware"));
+    assert!(glob_match("a[[:space:]]b", "a b"));
+    assert!(glob_match("a[[:space:]]b", "a\tb"));
+    assert!(glob_match("a[[:blank:]]b", "a\tb"));
+    assert!(!glob_match("a[[:blank:]]b", "a\nb"));
+    assert!(glob_match("[[:punct:]]", "!"));
+    assert!(glob_match("[[:punct:]]", "~"));
+    assert!(!glob_match("[[:punct:]]", "a"));
+    assert!(glob_match("0x[[:xdigit:]][[:xdigit:]]", "0xfF"));
+    assert!(!glob_match("0x[[:xdigit:]]", "0xg"));
+    assert!(glob_match("[[:cntrl:]]", "\x7f"));
+    assert!(glob_match("[[:graph:]]", "#"));
+    assert!(!glob_match("[[:graph:]]", " "));
+    assert!(glob_match("[[:print:]]", " "));
+    assert!(glob_match("[[:word:]]", "_"));
+
+    assert!(glob_match("[[:alnum:]_]*", "_private"));
+    assert!(glob_match("[[:alnum:]_]*", "a1"));
+    assert!(!glob_match("[[:alnum:]_]*", "-flag"));
+    assert!(glob_match("[[:digit:]a-c]", "b"));
+    assert!(glob_match("[[:digit:]a-c]", "7"));
+    assert!(!glob_match("[[:digit:]a-c]", "d"));
+    assert!(glob_match("[![:alpha:]]", "1"));
+    assert!(!glob_match("[![:alpha:]]", "a"));
+    assert!(glob_match("[^[:upper:][:digit:]]", "a"));
+    assert!(!glob_match("[^[:upper:][:digit:]]", "5"));
+    assert!(glob_match("**/[[:upper:]]*.md", "docs/README.md"));
+
+    // A `[:` without a closing `:]` is a literal `[`.
+    assert!(glob_match("[[:a]", ":"));
+    assert!(glob_match("[[:a]", "["));
+    assert!(glob_match("[[:]", ":"));
+
+    let options = MatchOptions::new().case_insensitive(true);
+    let glob = Glob::with_options("[[:upper:]]", options).unwrap();
+    assert!(glob.is_match("a"));
+
+    let glob = Glob::with_options("[[:alpha:]]", MatchOptions::new().utf8(true)).unwrap();
+    assert!(glob.is_match("a"));
+    assert!(!glob.is_match("é"));
+
+    assert_eq!(
+      Glob::new("a[[:alpah:]]").unwrap_err().kind(),
+      ErrorKind::UnknownClass
+    );
+    assert_eq!(Glob::new("a[[:alpah:]]").unwrap_err().span(), 2..11);
+    assert!(!glob_match("[[:alpah:]]", "a"));
+  }
+
   #[test]
   fn bash_wildmatch() {
     assert!(!glob_match("a[]-]b", "aab"));
@@ -1336,6 +1400,10 @@ mod tests {
     assert!(glob_match("*!*.md", "foo!bar.md"));
     assert!(glob_match("*b*.md", "foobar.md"));
 
+    assert!(!glob_match("!**/*.md", "a.md"));
+    assert!(!glob_match("!**/*.md", "a/b/c.md"));
+    assert!(glob_match("!**/*.md", "a/b/c.js"));
+
     assert!(!glob_match("a!!b", "a"));
     assert!(!glob_match("a!!b", "aa"));
     assert!(!glob_match("a!!b", "a/b"));
@@ -1752,6 +1820,1978 @@ mod tests {
     }
   }
 
+  #[test]
+  fn pre_compiled() {
+    let glob = Glob::new("some/**/n*d[k-m]e?txt").unwrap();
+    assert!(glob.is_match("some/a/bigger/path/to/the/crazy/needle.txt"));
+    assert!(glob.is_match("some/needle.txt"));
+    assert!(!glob.is_match("some/a/bigger/path/to/the/crazy/needle.png"));
+    assert!(!glob.is_match("other/needle.txt"));
+
+    let glob = Glob::new("!{a,b}/**/*.md").unwrap();
+    assert!(!glob.is_match("a/b/c.md"));
+    assert!(glob.is_match("c/b/c.md"));
+    assert!(glob.is_match("a/b/c.js"));
+
+    let input = std::fs::read_to_string("tests/fixtures/input.txt").unwrap();
+    let glob =
+      Glob::new("{src,extensions}/**/test/**/{fixtures,browser,common}/**/*.{ts,js}").unwrap();
+    let matched = input
+      .lines()
+      .filter(|line| glob.is_match(line))
+      .collect::<Vec<_>>();
+    let expected = std::fs::read_to_string("tests/fixtures/matched-pattern-1.txt").unwrap();
+    assert_eq!(matched, expected.lines().collect::<Vec<_>>());
+  }
+
+  #[test]
+  fn glob_set() {
+    let set = GlobSet::new([
+      "src/**/*.ts",
+      "src/*.rs",
+      "src/lib.rs",
+      "**/*.md",
+      "!src/**",
+    ])
+    .unwrap();
+    assert_eq!(set.len(), 5);
+    assert_eq!(set.matches("src/lib.rs"), vec![1, 2]);
+    assert_eq!(set.matches("src/a/b.ts"), vec![0]);
+    assert_eq!(set.matches("docs/README.md"), vec![3, 4]);
+    assert_eq!(set.matches("src/README.md"), vec![3]);
+    assert_eq!(set.matches("src"), vec![4]);
+    assert_eq!(set.first_match("src/lib.rs"), Some(1));
+    assert_eq!(set.first_match("tests/test.rs"), Some(4));
+    assert!(set.is_match("src/a/b.ts"));
+    assert!(!set.is_match("src/a/b.js"));
+
+    let set = GlobSet::new(Vec::<&str>::new()).unwrap();
+    assert!(set.is_empty());
+    assert!(!set.is_match("a"));
+    assert_eq!(set.first_match("a"), None);
+
+    let expected = (1..=10)
+      .map(|i| {
+        std::fs::read_to_string(format!("tests/fixtures/matched-pattern-{}.txt", i)).unwrap()
+      })
+      .collect::<Vec<_>>();
+    let set = GlobSet::new([
+      "{src,extensions}/**/test/**/{fixtures,browser,common}/**/*.{ts,js}",
+      "{extensions,src}/**/{media,images,icons}/**/*.{svg,png,gif,jpg}",
+      "{.github,build,test}/**/{workflows,azure-pipelines,integration,smoke}/**/*.{yml,yaml,json}",
+      "src/vs/{base,editor,platform,workbench}/test/{browser,common,node}/**/[a-z]*[tT]est.ts",
+      "src/vs/workbench/{contrib,services}/**/*{Editor,Workspace,Terminal}*.ts",
+      "{extensions,src}/**/{markdown,json,javascript,typescript}/**/*.{ts,json}",
+      "**/{electron-sandbox,electron-main,browser,node}/**/{*[sS]ervice*,*[cC]ontroller*}.ts",
+      "{src,extensions}/**/{common,browser,electron-sandbox}/**/*{[cC]ontribution,[sS]ervice}.ts",
+      "src/vs/{base,platform,workbench}/**/{test,browser}/**/*{[mM]odel,[cC]ontroller}*.ts",
+      "extensions/**/{browser,common,node}/{**/*[sS]ervice*,**/*[pP]rovider*}.ts",
+    ])
+    .unwrap();
+
+    let input = std::fs::read_to_string("tests/fixtures/input.txt").unwrap();
+    let mut matched = vec![vec![]; expected.len()];
+    for line in input.lines() {
+      for index in set.matches(line) {
+        matched[index].push(line);
+      }
+    }
+
+    for (matched, expected) in matched.iter().zip(&expected) {
+      assert_eq!(*matched, expected.lines().collect::<Vec<_>>());
+    }
+  }
+
+  #[test]
+  fn errors() {
+    let error = |glob: &str| {
+      let err = Glob::new(glob).unwrap_err();
+      (err.kind(), err.span())
+    };
+
+    assert_eq!(error("[abc"), (ErrorKind::UnclosedClass, 0..1));
+    assert_eq!(error("src/[a-z.rs"), (ErrorKind::UnclosedClass, 4..5));
+    assert_eq!(error("[]"), (ErrorKind::UnclosedClass, 0..1));
+    assert_eq!(error("{a,b"), (ErrorKind::UnclosedBrace, 0..1));
+    assert_eq!(error("{a,{b}"), (ErrorKind::UnclosedBrace, 0..1));
+    assert_eq!(error("!{a,b}/{c"), (ErrorKind::UnclosedBrace, 7..8));
+    assert_eq!(error("a\\"), (ErrorKind::DanglingEscape, 1..2));
+    assert_eq!(error("[a\\"), (ErrorKind::DanglingEscape, 2..3));
+    assert_eq!(error("[z-a]"), (ErrorKind::EmptyRange, 1..4));
+    assert_eq!(error("[a\\z-\\a]"), (ErrorKind::EmptyRange, 2..7));
+    let options = MatchOptions::new().max_brace_depth(10);
+    assert_eq!(
+      Glob::with_options("{{{{{{{{{{{a}}}}}}}}}}}", options)
+        .unwrap_err()
+        .span(),
+      10..11
+    );
+    assert_eq!(
+      Glob::with_options("{a}{b}{c}{d}{e}{f}{g}{h}{i}{j}{k}", options)
+        .unwrap_err()
+        .kind(),
+      ErrorKind::NestingTooDeep
+    );
+
+    assert!(Glob::with_options("{{{{{{{{{{a}}}}}}}}}}", options).is_ok());
+    assert!(Glob::with_options("{a,{b,{c,{d}}}}{{{{{{e}}}}}}", options).is_ok());
+    assert!(Glob::new("{a,}}").is_ok());
+    assert!(Glob::new("[]]").is_ok());
+
+    let err = GlobSet::new(["*.rs", "{a"]).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::UnclosedBrace);
+    assert_eq!(err.to_string(), "unclosed brace at byte 0");
+
+    // `glob_match` treats malformed patterns as never matching.
+    assert!(!glob_match("[abc", "a"));
+    assert!(!glob_match("{a,b", "a"));
+    assert!(!glob_match("a\\", "a"));
+    assert!(glob_match("![abc", "a"));
+    assert!(glob_match("[!z-a]", "b"));
+  }
+
+  #[test]
+  fn captures() {
+    fn test_captures<'a>(glob: &str, path: &'a str) -> Option<Vec<&'a str>> {
+      glob_match_with_captures(glob, path)
+        .map(|captures| captures.into_iter().map(|range| &path[range]).collect())
+    }
+
+    assert_eq!(test_captures("a/b", "a/b"), Some(vec![]));
+    assert_eq!(test_captures("a/*/c", "a/bx/c"), Some(vec!["bx"]));
+    assert_eq!(test_captures("a/*/c", "a/test/c"), Some(vec!["test"]));
+    assert_eq!(
+      test_captures("a/*/c/*/e", "a/b/c/d/e"),
+      Some(vec!["b", "d"])
+    );
+    assert_eq!(test_captures("a/{b,x}/c", "a/b/c"), Some(vec!["b"]));
+    assert_eq!(test_captures("a/{b,x}/c", "a/x/c"), Some(vec!["x"]));
+    assert_eq!(test_captures("a/?/c", "a/b/c"), Some(vec!["b"]));
+    assert_eq!(test_captures("a/*?x/c", "a/yybx/c"), Some(vec!["yy", "b"]));
+    assert_eq!(
+      test_captures("a/*[a-z]x/c", "a/yybx/c"),
+      Some(vec!["yy", "b"])
+    );
+    assert_eq!(test_captures("a/{b*c,c}y", "a/bdcy"), Some(vec!["bdc"]));
+    assert_eq!(test_captures("a/{b*,c}y", "a/bdy"), Some(vec!["bd"]));
+    assert_eq!(test_captures("a/{b*c,c}", "a/bdc"), Some(vec!["bdc"]));
+    assert_eq!(test_captures("a/{b*,c}", "a/bd"), Some(vec!["bd"]));
+    assert_eq!(test_captures("a/{b*,c}", "a/c"), Some(vec!["c"]));
+    assert_eq!(test_captures("a/{b{c,d}}e", "a/bde"), Some(vec!["bd"]));
+    assert_eq!(
+      test_captures("a/{b{c,d},e}*", "a/bdefg"),
+      Some(vec!["bd", "efg"])
+    );
+    assert_eq!(
+      test_captures("a/{b{c,d},e}*", "a/efg"),
+      Some(vec!["e", "fg"])
+    );
+    assert_eq!(test_captures("a/{x,b}*", "a/bx"), Some(vec!["b", "x"]));
+    assert_eq!(
+      test_captures("**/*.js", "a/b.c/c.js"),
+      Some(vec!["a/b.c", "c"])
+    );
+    assert_eq!(test_captures("**/*.js", "c.js"), Some(vec!["", "c"]));
+    assert_eq!(
+      test_captures("**/**/*.js", "a/b.c/c.js"),
+      Some(vec!["a/b.c", "c"])
+    );
+    assert_eq!(
+      test_captures("**/*/*.js", "a/b.c/c.js"),
+      Some(vec!["a", "b.c", "c"])
+    );
+    assert_eq!(test_captures("a/**", "a/b/c"), Some(vec!["b/c"]));
+    assert_eq!(test_captures("a/**/b", "a/b"), Some(vec![""]));
+    assert_eq!(test_captures("*/a/*", "x/a/y"), Some(vec!["x", "y"]));
+    assert_eq!(
+      test_captures(
+        "some/**/n*d[k-m]e?txt",
+        "some/a/bigger/path/to/the/crazy/needle.txt"
+      ),
+      Some(vec!["a/bigger/path/to/the/crazy", "ee", "l", "."])
+    );
+    assert_eq!(test_captures("a/*/c", "a/b/d"), None);
+    assert_eq!(test_captures("!a/*", "b/c"), Some(vec![]));
+    assert_eq!(test_captures("!a/*", "a/c"), None);
+
+    let glob = Glob::new("src/**/*.{ts,js}").unwrap();
+    let captures = glob.captures("src/vs/base/index.js").unwrap();
+    assert_eq!(captures[0].range(), 4..11);
+    assert_eq!(captures[0].branch(), None);
+    assert_eq!(captures[1].range(), 12..17);
+    assert_eq!(captures[2].range(), 18..20);
+    assert_eq!(captures[2].branch(), Some(1));
+
+    let glob = Glob::new("{a,b}/*/{c,{d,e}}").unwrap();
+    let captures = glob.captures("b/x/e").unwrap();
+    assert_eq!(captures.len(), 3);
+    assert_eq!(captures[0].branch(), Some(1));
+    assert_eq!(captures[1].range(), 2..3);
+    assert_eq!(captures[2].range(), 4..5);
+    assert_eq!(captures[2].branch(), Some(1));
+  }
+
+  #[test]
+  fn case_insensitive() {
+    let options = MatchOptions::new().case_insensitive(true);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("*.PNG", "photo.png"));
+    assert!(glob_match("*.png", "PHOTO.PNG"));
+    assert!(glob_match("SRC/**/*.Rs", "src/a/B/lib.rs"));
+    assert!(glob_match("[a-c]", "B"));
+    assert!(glob_match("[A-C]", "b"));
+    assert!(glob_match("[!a-c]", "D"));
+    assert!(!glob_match("[!a-c]", "B"));
+    assert!(!glob_match("[!A-C]", "b"));
+    assert!(glob_match("[Z-a]", "z"));
+    assert!(glob_match("[Z-a]", "A"));
+    assert!(glob_match("[Z-a]", "_"));
+    assert!(!glob_match("[Z-a]", "b"));
+    assert!(glob_match("{FOO,bar}.txt", "foo.TXT"));
+    assert!(glob_match("{foo,{BAR,baz}}", "bar"));
+    assert!(glob_match("\\A*", "abc"));
+    assert!(!glob_match("*.png", "photo.jpg"));
+    assert!(!glob_match("a", "b"));
+
+    assert!(glob_match("ÉTÉ/*.txt", "été/notes.TXT"));
+    assert!(glob_match("straße", "STRAẞE"));
+    assert!(glob_match("ΣΟΦΊΑ", "σοφία"));
+    assert!(glob_match("*ς", "ΛΌΓΟΣ"));
+    assert!(glob_match("日本/*", "日本/語"));
+    assert!(!glob_match("été", "ete"));
+
+    assert!(!Glob::new("*.PNG").unwrap().is_match("photo.png"));
+    assert!(!fast_glob::glob_match("[a-c]", "B"));
+
+    let set = GlobSet::with_options(["SRC/*.rs", "src/*.TOML", "*.md"], options).unwrap();
+    assert_eq!(set.matches("Src/Lib.RS"), vec![0]);
+    assert_eq!(set.matches("src/Cargo.toml"), vec![1]);
+    assert_eq!(set.matches("README.MD"), vec![2]);
+  }
+
+  #[test]
+  fn utf8_mode() {
+    let options = MatchOptions::new().utf8(true);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("caf?", "café"));
+    assert!(glob_match("?", "日"));
+    assert!(!glob_match("??", "日"));
+    assert!(glob_match("*??", "日本"));
+    assert!(!glob_match("*???", "日本"));
+    assert!(!glob_match("a*??", "a日"));
+    assert!(glob_match("[à-ÿ]t[é]", "été"));
+    assert!(!glob_match("[à-ÿ]", "a"));
+    assert!(glob_match("[!à-ÿ]", "a"));
+    assert!(!glob_match("[!à-ÿ]", "é"));
+    assert!(glob_match("[日本語]*.txt", "語録.txt"));
+    assert!(glob_match("[一-龥]?/*", "中文/a.txt"));
+    assert!(glob_match("[\\日]", "日"));
+    assert!(glob_match("フォルダ/**/[ア-ン]*.js", "フォルダ/a/アイ.js"));
+    assert!(glob_match("{[à-ÿ],?}x", "éx"));
+
+    // Invalid UTF-8 is matched a byte at a time.
+    let glob = Glob::with_options("a?b", options).unwrap();
+    assert!(glob.is_match(b"a\xffb"));
+    assert!(!glob.is_match(b"a\xff\xffb"));
+
+    let captures = Glob::with_options("*[é]?", options)
+      .unwrap()
+      .captures("aéé")
+      .unwrap();
+    assert_eq!(captures[1].range(), 1..3);
+    assert_eq!(captures[2].range(), 3..5);
+
+    let options = options.case_insensitive(true);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("[À-Ö]", "é"));
+    assert!(glob_match("[à-ö]", "É"));
+    assert!(glob_match("[a-c]", "B"));
+    assert!(!glob_match("[!À-Ö]", "é"));
+
+    assert!(!fast_glob::glob_match("caf?", "café"));
+    assert!(!Glob::new("[é]").unwrap().is_match("é"));
+  }
+
+  #[test]
+  fn extglob() {
+    let options = MatchOptions::new().extglob(true);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("@(src|lib)/**", "src/a/b.rs"));
+    assert!(glob_match("@(src|lib)/**", "lib/a.rs"));
+    assert!(!glob_match("@(src|lib)/**", "test/a.rs"));
+    assert!(!glob_match("@(src|lib)/**", "srclib/a.rs"));
+    assert!(glob_match("a.?(min.)js", "a.js"));
+    assert!(glob_match("a.?(min.)js", "a.min.js"));
+    assert!(!glob_match("a.?(min.)js", "a.min.min.js"));
+    assert!(glob_match("a*(b|c)d", "ad"));
+    assert!(glob_match("a*(b|c)d", "abcbd"));
+    assert!(!glob_match("a*(b|c)d", "abxd"));
+    assert!(!glob_match("+(a|b)", ""));
+    assert!(glob_match("+(a|b)", "abba"));
+    assert!(!glob_match("+(a|b)", "abc"));
+    assert!(glob_match("+(ab|a)c", "aababc"));
+    assert!(glob_match("*(*.js|*.ts)", "a.js"));
+    assert!(glob_match("@(a|@(b|+(c)))", "ccc"));
+    assert!(glob_match("@({a,b}c|d)", "bc"));
+    assert!(glob_match("{a,@(b|c)}x", "cx"));
+    assert!(glob_match("*.@(png|jp?(e)g)", "photo.jpeg"));
+    assert!(!glob_match("*.@(png|jp?(e)g)", "photo.gif"));
+    assert!(!glob_match("@(a|b)", "a/b"));
+
+    assert!(glob_match("!(*.test).ts", "index.ts"));
+    assert!(glob_match("!(*.test).ts", ".ts"));
+    assert!(!glob_match("!(*.test).ts", "index.test.ts"));
+    assert!(!glob_match("!(*.test).ts", "index.js"));
+    assert!(glob_match("src/**/!(*.test).ts", "src/a/index.ts"));
+    assert!(!glob_match("src/**/!(*.test).ts", "src/a/index.test.ts"));
+    assert!(!glob_match("!(*.test).ts", "a/index.ts"));
+    assert!(glob_match("!(foo)", "bar"));
+    assert!(glob_match("!(foo)", "foobar"));
+    assert!(!glob_match("!(foo)", "foo"));
+    assert!(glob_match("!(foo|bar)*", "baz"));
+    assert!(glob_match("a/!(b)/c", "a/x/c"));
+    assert!(!glob_match("a/!(b)/c", "a/b/c"));
+    assert!(!glob_match("a/!(b)/c", "a/x/y/c"));
+
+    // A group containing a separator may match across segments.
+    assert!(!glob_match("!(*/*)", "a/a"));
+    assert!(!glob_match("!(*/*)", "b/c"));
+    assert!(!glob_match("!(*/b)", "a/b"));
+    assert!(!glob_match("!(a/b)", "a/b"));
+    assert!(glob_match("!(*/*)", "a"));
+    assert!(glob_match("!(*/*)", "a.b"));
+    assert!(glob_match("!(*/b)", "a"));
+    assert!(glob_match("!(*/b)", "a/a"));
+    assert!(glob_match("!(*/b)", "b/c"));
+    assert!(glob_match("!(a/b)", "a.b"));
+    assert!(glob_match("!(a/b)", "b/b"));
+    assert!(glob_match("@(a/b|c)/d", "a/b/d"));
+
+    assert!(glob_match("!!(a)", "a"));
+    assert!(!glob_match("!!(a)", "b"));
+    assert!(glob_match("a|b", "a|b"));
+    assert!(glob_match("(a)", "(a)"));
+    assert!(glob_match("{(,)}", ")"));
+
+    let captures = Glob::with_options("*.@(png|jp?(e)g)", options)
+      .unwrap()
+      .captures("photo.jpeg")
+      .unwrap();
+    assert_eq!(captures[0].range(), 0..5);
+    assert_eq!(captures[1].range(), 6..10);
+    assert_eq!(captures[1].branch(), None);
+
+    assert_eq!(
+      Glob::with_options("a@(b|c", options).unwrap_err().span(),
+      1..3
+    );
+    assert_eq!(
+      Glob::with_options("a@(b|c", options).unwrap_err().kind(),
+      ErrorKind::UnclosedGroup
+    );
+
+    assert!(fast_glob::glob_match("@(a|b)", "@(a|b)"));
+    assert!(fast_glob::glob_match("!(a)", "(b)"));
+
+    // Groups count toward the nesting limit like braces.
+    let nested = |depth: usize| format!("{}a{}", "@(".repeat(depth), ")".repeat(depth));
+    assert!(glob_match(&nested(64), "a"));
+    assert!(glob_match(&format!("{{x,{}}}", nested(63)), "a"));
+    assert!(Glob::with_options(format!("{{x,{}}}", nested(64)), options).is_err());
+    let err = Glob::with_options(nested(10000), options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NestingTooDeep);
+    assert_eq!(err.span(), 128..130);
+    assert_eq!(
+      super::to_regex(nested(10000), options).unwrap_err().kind(),
+      ErrorKind::NestingTooDeep
+    );
+  }
+
+  #[test]
+  fn sequence_braces() {
+    assert!(glob_match("{1..10}", "1"));
+    assert!(glob_match("{1..10}", "7"));
+    assert!(glob_match("{1..10}", "10"));
+    assert!(!glob_match("{1..10}", "0"));
+    assert!(!glob_match("{1..10}", "11"));
+    assert!(!glob_match("{1..10}", "07"));
+    assert!(!glob_match("{1..10}", "1..10"));
+    assert!(glob_match("{10..1}", "3"));
+    assert!(glob_match("{-3..3}", "-2"));
+    assert!(glob_match("{-3..3}", "0"));
+    assert!(!glob_match("{-3..3}", "-0"));
+    assert!(!glob_match("{-3..3}", "-4"));
+    assert!(glob_match("{1..3}0", "10"));
+    assert!(glob_match("{1..3}0", "30"));
+    assert!(!glob_match("{1..3}0", "40"));
+
+    assert!(glob_match("part-{0001..0128}.parquet", "part-0001.parquet"));
+    assert!(glob_match("part-{0001..0128}.parquet", "part-0128.parquet"));
+    assert!(!glob_match(
+      "part-{0001..0128}.parquet",
+      "part-0129.parquet"
+    ));
+    assert!(!glob_match("part-{0001..0128}.parquet", "part-1.parquet"));
+    assert!(!glob_match(
+      "part-{0001..0128}.parquet",
+      "part-00001.parquet"
+    ));
+    assert!(glob_match("{01..3}", "02"));
+    assert!(!glob_match("{01..3}", "2"));
+    assert!(glob_match("{-05..05}", "-05"));
+    assert!(glob_match("{-05..05}", "000"));
+    assert!(glob_match("{-05..05}", "005"));
+
+    assert!(glob_match("{01..20..2}", "01"));
+    assert!(glob_match("{01..20..2}", "19"));
+    assert!(!glob_match("{01..20..2}", "02"));
+    assert!(!glob_match("{01..20..2}", "20"));
+    assert!(glob_match("{10..1..3}", "4"));
+    assert!(!glob_match("{10..1..3}", "5"));
+    assert!(glob_match("{1..5..-2}", "3"));
+    assert!(glob_match("{1..5..0}", "2"));
+    assert!(glob_match(
+      "{-9223372036854775808..9223372036854775807..2}",
+      "4"
+    ));
+    assert!(!glob_match(
+      "{-9223372036854775808..9223372036854775807..2}",
+      "-3"
+    ));
+    assert!(glob_match(
+      "{9223372036854775807..-9223372036854775808..3}",
+      "-2"
+    ));
+
+    assert!(glob_match("{a..e}", "c"));
+    assert!(!glob_match("{a..e}", "f"));
+    assert!(glob_match("{e..a}", "a"));
+    assert!(glob_match("{a..e..2}", "c"));
+    assert!(!glob_match("{a..e..2}", "d"));
+    assert!(glob_match("{Z..b}", "_"));
+
+    assert!(glob_match("{a,{1..3}}.txt", "2.txt"));
+    assert!(glob_match("{a,{1..3}}.txt", "a.txt"));
+    assert!(!glob_match("{a,{1..3}}.txt", "4.txt"));
+    assert!(glob_match("{{1..3},x..y}", "x..y"));
+    assert!(glob_match("v{1..3}.{0..9}.*", "v2.7.1"));
+    assert!(glob_match("**/shard-{1..100}/*", "data/shard-42/a.csv"));
+    assert!(glob_match("*{1..3}", "abc2"));
+
+    // Anything else between braces is a regular brace.
+    assert!(glob_match("{a..5}", "a..5"));
+    assert!(glob_match("{1..2..}", "1..2.."));
+    assert!(glob_match("{1..2..3..4}", "1..2..3..4"));
+
+    let glob = Glob::with_options("{A..C}", MatchOptions::new().case_insensitive(true)).unwrap();
+    assert!(glob.is_match("b"));
+
+    let path = "log-07-b.txt";
+    let captures = Glob::new("log-{01..10}-{a..c}.txt")
+      .unwrap()
+      .captures(path)
+      .unwrap();
+    assert_eq!(&path[captures[0].range()], "07");
+    assert_eq!(&path[captures[1].range()], "b");
+  }
+
+  #[test]
+  fn expand_braces() {
+    fn expand(pattern: &str) -> Vec<String> {
+      fast_glob::expand_braces(pattern).collect()
+    }
+
+    assert_eq!(
+      expand("{src,test}/**/*.{ts,tsx}"),
+      [
+        "src/**/*.ts",
+        "src/**/*.tsx",
+        "test/**/*.ts",
+        "test/**/*.tsx"
+      ]
+    );
+    assert_eq!(
+      expand("{a,b}/{c,d}.json"),
+      ["a/c.json", "a/d.json", "b/c.json", "b/d.json"]
+    );
+    assert_eq!(expand("a{b,{c,d}e}f"), ["abf", "acef", "adef"]);
+    assert_eq!(expand("{a,}b"), ["ab", "b"]);
+    assert_eq!(expand("{a}"), ["a"]);
+    assert_eq!(expand("abc"), ["abc"]);
+    assert_eq!(expand(""), [""]);
+
+    assert_eq!(expand("[{,}]{a,b}"), ["[{,}]a", "[{,}]b"]);
+    assert_eq!(expand("{[a,b],c}"), ["[a,b]", "c"]);
+    assert_eq!(expand("{[[:alpha:],],c}"), ["[[:alpha:],]", "c"]);
+    assert_eq!(expand("{a\\,b,c}"), ["a\\,b", "c"]);
+    assert_eq!(expand("\\{a,b}"), ["\\{a,b}"]);
+    assert_eq!(expand("{é\\é,ü}"), ["é\\é", "ü"]);
+    assert_eq!(expand("{a,b"), ["{a,b"]);
+    assert_eq!(expand("{a,b}}"), ["a}", "b}"]);
+    assert_eq!(expand("a,b"), ["a,b"]);
+
+    assert_eq!(expand("v{1..3}"), ["v1", "v2", "v3"]);
+    assert_eq!(expand("{3..1}"), ["3", "2", "1"]);
+    assert_eq!(expand("{01..10..3}"), ["01", "04", "07", "10"]);
+    assert_eq!(expand("{a..e..2}"), ["a", "c", "e"]);
+    assert_eq!(
+      expand("{Z..a}"),
+      ["Z", "\\[", "\\\\", "\\]", "^", "_", "`", "a"]
+    );
+    assert_eq!(expand("{x,{1..2}}"), ["x", "1", "2"]);
+    assert_eq!(expand("{!1}.-bb"), ["\\!1.-bb"]);
+    assert_eq!(expand("!{!a,b}"), ["!\\!a", "!b"]);
+    assert_eq!(expand("a{!a,b}"), ["a!a", "ab"]);
+    assert_eq!(expand("{a,}!b"), ["a!b", "\\!b"]);
+    assert_eq!(
+      expand("{9223372036854775806..9223372036854775807}"),
+      ["9223372036854775806", "9223372036854775807"]
+    );
+    assert_eq!(
+      expand("{-9223372036854775807..-9223372036854775808}"),
+      ["-9223372036854775807", "-9223372036854775808"]
+    );
+
+    assert_eq!(expand("{1..100000}").len(), DEFAULT_EXPANSION_LIMIT);
+    assert_eq!(
+      expand_braces_with_limit("{a,b}{1..3}", 4).collect::<Vec<_>>(),
+      ["a1", "a2", "a3", "b1"]
+    );
+    assert_eq!(expand_braces_with_limit("a", 0).count(), 0);
+
+    // Matching a pattern is the same as matching any of its expansions.
+    for pattern in [
+      "some/**/{tob,crazy}/?*.{png,txt}",
+      "{a,{b,c}*}/{[a-c],x}{1..3}",
+      "{*.rs,src/{lib,main}.rs}",
+      "{Z..a}",
+      "{!1}.-bb",
+      "{!a,b}",
+      "{{!a,c},b}",
+      "{a,}!b",
+      "{ ..#}",
+    ] {
+      for path in [
+        "some/a/bigger/path/to/the/crazy/needle.txt",
+        "cat/b2",
+        "a/x3",
+        "a/x4",
+        "src/main.rs",
+        "main.rs",
+        "[",
+        "\\",
+        "a",
+        "b",
+        "!a",
+        "!1.-bb",
+        "!",
+        "!b",
+        "#",
+      ] {
+        let expansions = expand(pattern);
+        assert_eq!(
+          glob_match(pattern, path),
+          expansions.iter().any(|glob| glob_match(glob, path)),
+          "{pattern} {path}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn deep_braces() {
+    let nested = |depth: usize| format!("{}a{}", "{x,".repeat(depth), "}".repeat(depth));
+    let sequential = |count: usize| "{a,b}".repeat(count);
+
+    assert!(glob_match(nested(11), "a"));
+    assert!(glob_match(nested(11), "x"));
+    assert!(glob_match(nested(64), "a"));
+    assert!(!glob_match(nested(64), "b"));
+    assert!(glob_match(sequential(64), "ab".repeat(32)));
+    assert!(!glob_match(sequential(64), "ab".repeat(31)));
+
+    // Patterns entering more braces than allowed never match rather than panicking.
+    assert!(!glob_match(nested(65), "a"));
+    assert!(!glob_match(sequential(65), "a".repeat(65)));
+    assert!(glob_match(format!("!{}", nested(65)), "a"));
+    assert_eq!(
+      Glob::new(nested(65)).unwrap_err().kind(),
+      ErrorKind::NestingTooDeep
+    );
+
+    let options = MatchOptions::new().max_brace_depth(200);
+    let glob = Glob::with_options(nested(200), options).unwrap();
+    assert!(glob.is_match("a"));
+    assert!(glob.is_match("x"));
+    assert!(!glob.is_match("xa"));
+
+    let glob = Glob::with_options(sequential(200), options).unwrap();
+    assert!(glob.is_match("b".repeat(200)));
+    assert_eq!(glob.captures("b".repeat(200)).unwrap().len(), 200);
+
+    // Wide braces, and braces inside alternatives that are never entered together, only
+    // count once.
+    let wide = format!(
+      "{{{}}}",
+      (0..1000)
+        .map(|i| i.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+    );
+    assert!(glob_match(&wide, "999"));
+    assert!(!glob_match(&wide, "1000"));
+    let siblings = format!("{{{}}}", vec![nested(63); 100].join(","));
+    assert!(glob_match(&siblings, "x"));
+    assert!(glob_match(format!("{}{}", nested(32), nested(32)), "ax"));
+    assert!(!glob_match(format!("{}{}", nested(32), nested(33)), "ax"));
+  }
+
+  #[test]
+  fn backtracking_groups() {
+    // Each brace or extglob group tries the rest of the glob after each of its branches,
+    // which must not take exponential time to rule out a path.
+    let start = Instant::now();
+    assert!(!glob_match("{a,a}".repeat(24) + "b", "a".repeat(24)));
+    assert!(!glob_match("*{a,a}".repeat(24) + "b", "a".repeat(48)));
+    assert!(!glob_match("**/{a,a}".repeat(24) + "b", "a/".repeat(48)));
+    assert!(glob_match("{a,a}".repeat(24) + "b", "a".repeat(24) + "b"));
+
+    let glob = Glob::new("*{a,a}".repeat(24) + "b").unwrap();
+    assert!(glob.captures("a".repeat(48)).is_none());
+    assert_eq!(glob.captures("a".repeat(48) + "b").unwrap().len(), 48);
+
+    let options = MatchOptions::new().extglob(true);
+    let glob = Glob::with_options("*(a)".repeat(24) + "b", options).unwrap();
+    assert!(!glob.is_match("a".repeat(48)));
+    assert!(glob.is_match("a".repeat(48) + "b"));
+    assert!(
+      start.elapsed() < Duration::from_secs(5),
+      "{:?}",
+      start.elapsed()
+    );
+  }
+
+  #[test]
+  fn separators() {
+    let glob_match = |separator: Separator, glob: &str, path: &str| {
+      let options = MatchOptions::new().separator(separator);
+      Glob::with_options(glob, options).unwrap().is_match(path)
+    };
+
+    assert!(glob_match(Separator::Slash, "a/*", "a/b"));
+    assert!(glob_match(Separator::Slash, "a/*", "a/b\\c"));
+    assert!(!glob_match(Separator::Slash, "a/*", "a\\b"));
+    assert!(glob_match(Separator::Slash, "a?b", "a\\b"));
+    assert!(glob_match(Separator::Slash, "**/*.rs", "src\\main.rs"));
+
+    assert!(glob_match(Separator::Backslash, "a/*", "a\\b"));
+    assert!(!glob_match(Separator::Backslash, "a/*", "a/b"));
+    assert!(glob_match(Separator::Backslash, "a*", "a/b"));
+    assert!(!glob_match(Separator::Backslash, "a*", "a\\b"));
+    assert!(!glob_match(Separator::Backslash, "a?b", "a\\b"));
+    assert!(glob_match(
+      Separator::Backslash,
+      "src/**/*.rs",
+      "src\\a\\b\\c.rs"
+    ));
+    assert!(glob_match(Separator::Backslash, "src/**", "src\\a/b"));
+    assert!(!glob_match(Separator::Backslash, "src/*", "src\\a\\b"));
+
+    assert!(glob_match(Separator::Both, "a/*", "a/b"));
+    assert!(glob_match(Separator::Both, "a/*", "a\\b"));
+    assert!(!glob_match(Separator::Both, "a/*", "a\\b/c"));
+    assert!(!glob_match(Separator::Both, "a?b", "a\\b"));
+    assert!(glob_match(Separator::Both, "src/**/*.rs", "src/a\\b/c.rs"));
+    assert!(glob_match(Separator::Both, "**/{a,b}/*", "x\\b\\c"));
+
+    let options = MatchOptions::new().separator(Separator::Both);
+    let captures = Glob::with_options("src/**/*.rs", options)
+      .unwrap()
+      .captures("src\\a\\b\\c.rs")
+      .unwrap();
+    assert_eq!(captures[0].range(), 4..7);
+
+    let options = MatchOptions::new().separator(Separator::Backslash);
+    let set = GlobSet::from_iter([
+      Glob::new("src/*.rs").unwrap(),
+      Glob::with_options("src/*.rs", options).unwrap(),
+    ]);
+    assert_eq!(set.matches("src/main.rs"), vec![0]);
+    assert_eq!(set.matches("src\\main.rs"), vec![1]);
+
+    assert_eq!(
+      MatchOptions::new(),
+      MatchOptions::new().separator(if cfg!(windows) {
+        Separator::Both
+      } else {
+        Separator::Slash
+      })
+    );
+  }
+
+  #[test]
+  fn dot() {
+    let options = MatchOptions::new().dot(false);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(!glob_match("*", ".git"));
+    assert!(glob_match(".*", ".git"));
+    assert!(glob_match("*", "a.b"));
+    assert!(glob_match("*.*", "a.b"));
+    assert!(!glob_match("*.js", ".js"));
+    assert!(!glob_match("?git", ".git"));
+    assert!(!glob_match("[.]git", ".git"));
+    assert!(!glob_match("[!a]git", ".git"));
+    assert!(glob_match("a?b", "a.b"));
+    assert!(glob_match(".[a-z]*", ".gitignore"));
+    assert!(!glob_match("src/*", "src/.env"));
+    assert!(glob_match("src/.*", "src/.env"));
+    assert!(glob_match("*/*", "a/b"));
+    assert!(!glob_match("*/*", "a/.b"));
+
+    assert!(!glob_match("**", ".git"));
+    assert!(!glob_match("**", "a/.git"));
+    assert!(!glob_match("a/**", "a/.git/config"));
+    assert!(glob_match("a/**", "a/b/c"));
+    assert!(glob_match("**/*.js", "a/b/c.js"));
+    assert!(!glob_match("**/*.js", "a/.b/c.js"));
+    assert!(!glob_match("**/*.js", ".a/c.js"));
+    assert!(!glob_match("**/*", ".git/config"));
+    assert!(glob_match(".git/**", ".git/config"));
+    assert!(glob_match("**/.git/*", "a/.git/config"));
+    assert!(glob_match("**/.git/*", ".git/config"));
+    assert!(glob_match("**/.*", "a/.env"));
+    assert!(!glob_match("**/*", "a/.env"));
+    assert!(glob_match("**/a/**/b", "x/a/y/b"));
+    assert!(!glob_match("**/a/**/b", "x/a/.y/b"));
+
+    assert!(!glob_match("{*,x}", ".x"));
+    assert!(glob_match("{*,.x}", ".x"));
+    assert!(glob_match("a{*,b}", "a.x"));
+
+    let options = options.extglob(true);
+    assert!(!Glob::with_options("@(*)", options).unwrap().is_match(".a"));
+    assert!(!Glob::with_options("!(x)", options).unwrap().is_match(".a"));
+    assert!(Glob::with_options(".!(x)", options).unwrap().is_match(".a"));
+    assert!(Glob::with_options("@(.a)", options).unwrap().is_match(".a"));
+    assert!(Glob::with_options("@(.*)", options).unwrap().is_match(".a"));
+    assert!(Glob::with_options("?(.a)", options).unwrap().is_match(".a"));
+    assert!(!Glob::with_options("@(*|.a)", options)
+      .unwrap()
+      .is_match(".b"));
+    assert!(!Glob::with_options("*(x)*", options).unwrap().is_match(".a"));
+    assert!(!Glob::with_options("!(.a)", options).unwrap().is_match(".b"));
+
+    let options = options.separator(Separator::Both);
+    assert!(!Glob::with_options("*/*", options)
+      .unwrap()
+      .is_match("a\\.b"));
+
+    let set = GlobSet::with_options(["src/*", "src/.*"], MatchOptions::new().dot(false)).unwrap();
+    assert_eq!(set.matches("src/.env"), vec![1]);
+    assert_eq!(set.matches("src/main.rs"), vec![0]);
+
+    assert!(Glob::new("*").unwrap().is_match(".git"));
+    assert!(fast_glob::glob_match("**/*", ".git/config"));
+  }
+
+  #[test]
+  fn literal_separator() {
+    let options = MatchOptions::new().literal_separator(false);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("*", "a/b/c"));
+    assert!(glob_match("a*c", "a/b/c"));
+    assert!(glob_match("a?c", "a/c"));
+    assert!(glob_match("*/c", "a/b/c"));
+    assert!(glob_match("*/b/*", "a/x/b/y/z"));
+    assert!(glob_match("logs/*.gz", "logs/2024/01/app.gz"));
+    assert!(!glob_match("logs/*.gz", "logs/2024/01/app.txt"));
+    assert!(!glob_match("*/c", "c"));
+    assert!(glob_match("**/c", "a/b/c"));
+    assert!(glob_match("a/**/*.js", "a/b/c/d.js"));
+    assert!(glob_match("{x,*}/d", "a/b/c/d"));
+    assert!(glob_match("*.{js,ts}", "a/b.ts"));
+
+    let extglob = options.extglob(true);
+    assert!(Glob::with_options("@(*.js)", extglob)
+      .unwrap()
+      .is_match("a/b.js"));
+
+    let both = options.separator(Separator::Both);
+    assert!(Glob::with_options("a/*", both).unwrap().is_match("a\\b/c"));
+
+    // Wildcards spanning separators still don't match the leading dots after them.
+    let no_dot = options.dot(false);
+    assert!(!Glob::with_options("*", no_dot).unwrap().is_match("a/.git"));
+    assert!(!Glob::with_options("a/*", no_dot)
+      .unwrap()
+      .is_match("a/b/.c"));
+    assert!(!Glob::with_options("*c", no_dot).unwrap().is_match("a/.c"));
+    assert!(Glob::with_options("*", no_dot).unwrap().is_match("a/b.c/d"));
+    assert!(Glob::with_options("*.git", no_dot)
+      .unwrap()
+      .is_match("a/.git"));
+
+    assert!(!Glob::new("*").unwrap().is_match("a/b"));
+    assert!(!Glob::new("a?c").unwrap().is_match("a/c"));
+    assert!(!Glob::new("*/c").unwrap().is_match("a/b/c"));
+  }
+
+  #[test]
+  fn backslash_escape() {
+    let options = MatchOptions::new()
+      .backslash_escape(false)
+      .separator(Separator::Slash);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("a\\*", "a\\b"));
+    assert!(!glob_match("a\\*", "a*"));
+    assert!(glob_match("\\n", "\\n"));
+    assert!(glob_match("[\\]]", "\\]"));
+    assert!(glob_match("[a\\]", "\\"));
+    assert!(glob_match("a\\", "a\\"));
+    assert!(!glob_match("a\\b", "a/b"));
+
+    let options = options.separator(Separator::Both);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("src\\*.rs", "src\\main.rs"));
+    assert!(glob_match("src\\*.rs", "src/main.rs"));
+    assert!(!glob_match("src\\*.rs", "src\\bin\\main.rs"));
+    assert!(glob_match("src\\**\\*.rs", "src\\bin\\main.rs"));
+    assert!(glob_match("src\\**\\*.rs", "src/main.rs"));
+    assert!(glob_match("src\\**", "src\\a\\b"));
+    assert!(glob_match("**\\**\\*.rs", "a\\b\\c.rs"));
+    assert!(glob_match("{src,lib}\\*.rs", "lib\\main.rs"));
+
+    let options = MatchOptions::new().separator(Separator::Both);
+    assert!(Glob::with_options("a\\*", options).unwrap().is_match("a*"));
+    assert!(!Glob::with_options("a\\*", options)
+      .unwrap()
+      .is_match("a\\b"));
+    assert_eq!(
+      Glob::new("a\\").unwrap_err().kind(),
+      ErrorKind::DanglingEscape
+    );
+  }
+
+  #[test]
+  fn basename() {
+    let options = MatchOptions::new().basename(true);
+    let glob_match =
+      |glob: &str, path: &str| Glob::with_options(glob, options).unwrap().is_match(path);
+
+    assert!(glob_match("*.rs", "lib.rs"));
+    assert!(glob_match("*.rs", "src/lib.rs"));
+    assert!(glob_match("*.rs", "src/bin/main.rs"));
+    assert!(!glob_match("*.rs", "src/lib.rs/mod.ts"));
+    assert!(!glob_match("*.rs", "src/"));
+    assert!(glob_match("lib.{rs,ts}", "src/lib.ts"));
+    assert!(glob_match("[a-c]*", "z/z/b"));
+    assert!(glob_match("**", "a/b/c"));
+    assert!(!glob_match("!*.rs", "src/lib.rs"));
+    assert!(glob_match("!*.rs", "src/lib.ts"));
+    assert!(!glob_match("*.rs", "src\\lib.ts"));
+    assert!(!Glob::new("*.rs").unwrap().is_match("src/lib.rs"));
+
+    // Patterns with a separator match whole paths.
+    assert!(glob_match("src/*.rs", "src/lib.rs"));
+    assert!(!glob_match("src/*.rs", "a/src/lib.rs"));
+    assert!(!glob_match("{src/lib,main}.rs", "a/src/lib.rs"));
+    assert!(glob_match("{src/lib,main}.rs", "main.rs"));
+    assert!(!glob_match("{src/lib,main}.rs", "a/main.rs"));
+    assert!(!glob_match("{a,**/b}", "x/a"));
+    assert!(glob_match("{a,**/b}", "x/b"));
+    assert!(glob_match("{a,**/b}", "a"));
+    assert_eq!(
+      super::to_regex("**/*.rs", options).unwrap(),
+      super::to_regex("**/*.rs", MatchOptions::new()).unwrap()
+    );
+    let glob = Glob::with_options("**/*.rs", options.dot(false)).unwrap();
+    assert!(!glob.is_match(".git/a.rs"));
+    assert_eq!(glob.could_match_descendant(".git"), DescendantMatch::No);
+
+    let glob = Glob::with_options("*.{rs,ts}", options).unwrap();
+    let path = "src/a/lib.ts";
+    let captures = glob.captures(path).unwrap();
+    assert_eq!(&path[captures[0].range()], "lib");
+    assert_eq!(&path[captures[1].range()], "ts");
+    assert_eq!(glob.could_match_descendant("src/a"), DescendantMatch::Maybe);
+
+    let options = options.separator(Separator::Both);
+    assert!(Glob::with_options("*.rs", options)
+      .unwrap()
+      .is_match("src\\lib.rs"));
+
+    let options = MatchOptions::new().basename(true).dot(false);
+    assert!(!Glob::with_options("*", options)
+      .unwrap()
+      .is_match("src/.env"));
+    assert!(Glob::with_options(".*", options)
+      .unwrap()
+      .is_match("src/.env"));
+
+    let set = GlobSet::with_options(
+      ["src/*.ts", "*.rs", "!*.md"],
+      MatchOptions::new().basename(true),
+    )
+    .unwrap();
+    assert_eq!(set.matches("src/lib.rs"), vec![1, 2]);
+    assert_eq!(set.matches("src/lib.ts"), vec![0, 2]);
+    assert_eq!(set.matches("src/a/b.md"), Vec::<usize>::new());
+  }
+
+  #[test]
+  fn pattern_analysis() {
+    let glob = |glob: &str| Glob::new(glob).unwrap();
+
+    for pattern in [
+      "",
+      "src/lib.rs",
+      "src/",
+      "/usr/lib",
+      "src/\\*.rs",
+      "a\\{b,c}",
+      "a\\[b]",
+    ] {
+      assert!(glob(pattern).is_literal(), "{pattern}");
+      assert!(!glob(pattern).has_magic(), "{pattern}");
+    }
+
+    for pattern in ["{a,b}/c", "src/{lib,main}.rs", "file{1..3}", "{a,{b,c}}"] {
+      assert!(!glob(pattern).is_literal(), "{pattern}");
+      assert!(!glob(pattern).has_magic(), "{pattern}");
+    }
+
+    for pattern in [
+      "*",
+      "src/**",
+      "a?c",
+      "[abc]",
+      "{a,b*}",
+      "!src/lib.rs",
+      "a/**/b",
+      "[!a]",
+    ] {
+      assert!(!glob(pattern).is_literal(), "{pattern}");
+      assert!(glob(pattern).has_magic(), "{pattern}");
+    }
+
+    let options = MatchOptions::new().extglob(true);
+    assert!(Glob::with_options("@(a|b)", options).unwrap().has_magic());
+    let options = MatchOptions::new().case_insensitive(true);
+    assert!(Glob::with_options("a.rs", options).unwrap().has_magic());
+    assert!(!Glob::with_options("1.2", options).unwrap().has_magic());
+    let options = MatchOptions::new().basename(true);
+    assert!(Glob::with_options("lib.rs", options).unwrap().has_magic());
+    assert!(Glob::with_options("src/lib.rs", options)
+      .unwrap()
+      .is_literal());
+
+    assert_eq!(glob("src/vs/**/test/*.ts").static_base(), b"src/vs");
+    assert_eq!(glob("src/vs/*.ts").static_base(), b"src/vs");
+    assert_eq!(glob("src/lib.rs").static_base(), b"src");
+    assert_eq!(glob("src/\\*/*.rs").static_base(), b"src/*");
+    assert_eq!(glob("src/a{b,c}/d").static_base(), b"src");
+    assert_eq!(glob("src/ab*/d").static_base(), b"src");
+    assert_eq!(glob("{a,b}/c").static_base(), b"");
+    assert_eq!(glob("**/*.rs").static_base(), b"");
+    assert_eq!(glob("lib.rs").static_base(), b"");
+    assert_eq!(glob("!src/**").static_base(), b"");
+  }
+
+  #[test]
+  fn could_match_descendant() {
+    use DescendantMatch::*;
+
+    let descendants = |glob: &str, dir: &str| Glob::new(glob).unwrap().could_match_descendant(dir);
+
+    let glob = "{src,extensions}/**/test/**/*.ts";
+    assert_eq!(descendants(glob, ""), Maybe);
+    assert_eq!(descendants(glob, "src"), Maybe);
+    assert_eq!(descendants(glob, "src/"), Maybe);
+    assert_eq!(descendants(glob, "src/vs/base"), Maybe);
+    assert_eq!(descendants(glob, "extensions/git/test"), Maybe);
+    assert_eq!(descendants(glob, "docs"), No);
+    assert_eq!(descendants(glob, "srcs"), No);
+
+    assert_eq!(descendants("src/*.ts", "src"), Maybe);
+    assert_eq!(descendants("src/*.ts", "src/a"), No);
+    assert_eq!(descendants("src/*", "src/a"), No);
+    assert_eq!(descendants("src/a*", "src/ab"), No);
+    assert_eq!(descendants("src/*/index.ts", "src/a"), Maybe);
+    assert_eq!(descendants("src/*/index.ts", "src/a/b"), No);
+    assert_eq!(descendants("*/b/c", "a/b"), Maybe);
+    assert_eq!(descendants("*/b/c", "a/x"), No);
+    assert_eq!(descendants("a/b/c", "a"), Maybe);
+    assert_eq!(descendants("a/b/c", "a/b/c"), No);
+    assert_eq!(descendants("**/node_modules/**/*.js", "a/b"), Maybe);
+    assert_eq!(descendants("{a/b,c}/d", "a"), Maybe);
+    assert_eq!(descendants("{a/b,c}/d", "a/c"), No);
+    assert_eq!(descendants("a{1..3}/b", "a2"), Maybe);
+    assert_eq!(descendants("a{1..3}/b", "a4"), No);
+
+    assert_eq!(descendants("**", ""), Yes);
+    assert_eq!(descendants("**", "a/b"), Yes);
+    assert_eq!(descendants("src/**", "src"), Yes);
+    assert_eq!(descendants("src/**", "src/vs"), Yes);
+    assert_eq!(descendants("src/**", "lib"), No);
+    assert_eq!(descendants("src/**/*", "src"), Maybe);
+    assert_eq!(descendants("{src,lib}/**", "lib/a"), Yes);
+
+    assert_eq!(descendants("!src/**", "src/a"), No);
+    assert_eq!(descendants("!src/**", "lib"), Yes);
+    assert_eq!(descendants("!src/*.ts", "src"), Maybe);
+
+    let options = MatchOptions::new().dot(false);
+    let glob = Glob::with_options("**", options).unwrap();
+    assert_eq!(glob.could_match_descendant("a"), Maybe);
+    assert_eq!(glob.could_match_descendant("a/.git"), No);
+
+    let options = MatchOptions::new().extglob(true);
+    let glob = Glob::with_options("@(a/b|c)/d", options).unwrap();
+    assert_eq!(glob.could_match_descendant("a"), Maybe);
+    let glob = Glob::with_options("src/!(test)/*.ts", options).unwrap();
+    assert_eq!(glob.could_match_descendant("src/lib"), Maybe);
+    assert_eq!(glob.could_match_descendant("src/test"), No);
+
+    let options = MatchOptions::new().literal_separator(false);
+    let glob = Glob::with_options("a*.ts", options).unwrap();
+    assert_eq!(glob.could_match_descendant("a/b"), Maybe);
+
+    let options = MatchOptions::new().separator(Separator::Both);
+    let glob = Glob::with_options("src/*/*.rs", options).unwrap();
+    assert_eq!(glob.could_match_descendant("src\\bin"), Maybe);
+    assert_eq!(glob.could_match_descendant("src\\bin\\x"), No);
+  }
+
+  fn walk_paths(root: &std::path::Path, patterns: &[&str]) -> Vec<String> {
+    let mut paths: Vec<String> = walk(root, patterns)
+      .map(|entry| {
+        let entry = entry.unwrap();
+        let path = entry.path().to_str().unwrap().replace('\\', "/");
+        if entry.is_dir() {
+          path + "/"
+        } else {
+          path
+        }
+      })
+      .collect();
+    paths.sort();
+    paths
+  }
+
+  #[test]
+  fn walk_tree() {
+    let dir = tempfile::tempdir().unwrap();
+    for path in [
+      "Cargo.toml",
+      "README.md",
+      "src/lib.rs",
+      "src/glob.rs",
+      "src/bin/main.rs",
+      "src/bin/.hidden.rs",
+      "tests/test.rs",
+      "tests/fixtures/input.txt",
+      "docs/src/index.md",
+      "target/debug/build.rs",
+    ] {
+      let path = dir.path().join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, "").unwrap();
+    }
+
+    assert_eq!(
+      walk_paths(dir.path(), &["src/**/*.rs"]),
+      [
+        "src/bin/.hidden.rs",
+        "src/bin/main.rs",
+        "src/glob.rs",
+        "src/lib.rs"
+      ]
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["*.{toml,md}"]),
+      ["Cargo.toml", "README.md"]
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["**/*.md", "tests/*"]),
+      [
+        "README.md",
+        "docs/src/index.md",
+        "tests/fixtures/",
+        "tests/test.rs"
+      ]
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["src/bin/main.rs"]),
+      ["src/bin/main.rs"]
+    );
+    assert_eq!(walk_paths(dir.path(), &["*/"]), Vec::<String>::new());
+    assert_eq!(
+      walk_paths(dir.path(), &["src/**", "src/bin/*"]),
+      [
+        "src/bin/",
+        "src/bin/.hidden.rs",
+        "src/bin/main.rs",
+        "src/glob.rs",
+        "src/lib.rs"
+      ]
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["missing/**"]),
+      Vec::<String>::new()
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["Cargo.toml/*"]),
+      Vec::<String>::new()
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["src/../tests/*.rs", "./src/bin/../*.rs"]),
+      ["src/glob.rs", "src/lib.rs", "tests/test.rs"]
+    );
+    assert_eq!(
+      walk_paths(dir.path(), &["src/../*.toml", "src/bin/../../docs/**"]),
+      ["Cargo.toml", "docs/src/", "docs/src/index.md"]
+    );
+
+    let options = MatchOptions::new().dot(false);
+    let paths: Vec<_> = walk_with_options(dir.path(), ["src/bin/*"], options)
+      .map(|entry| entry.unwrap().into_path())
+      .collect();
+    assert_eq!(paths, [std::path::Path::new("src/bin/main.rs")]);
+
+    let mut entries = walk(dir.path(), ["src/[a-"]);
+    let err = entries.next().unwrap().unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+    assert!(entries.next().is_none());
+
+    // The walk never leaves the root, even for patterns starting outside it.
+    for pattern in [
+      "/etc/host*",
+      "../src/**",
+      "src/../../*",
+      "./../*",
+      "bin/../../**",
+    ] {
+      let mut entries = walk(dir.path().join("src"), ["*.rs", pattern]);
+      let err = entries.next().unwrap().unwrap_err();
+      assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+      assert!(entries.next().is_none());
+
+      let results: Vec<_> = walk_parallel(dir.path().join("src"), [pattern])
+        .into_iter()
+        .collect();
+      assert_eq!(results.len(), 1);
+      assert!(results[0].is_err());
+    }
+  }
+
+  #[test]
+  fn walk_prunes() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::create_dir_all(dir.path().join("src/a")).unwrap();
+    std::fs::write(dir.path().join("src/a/x.ts"), "").unwrap();
+    std::fs::write(dir.path().join("node_modules"), "").unwrap();
+
+    // A file where a directory is expected isn't read, so it doesn't fail the walk.
+    assert_eq!(
+      walk_paths(dir.path(), &["node_modules/**/*.ts", "src/**/*.ts"]),
+      ["src/a/x.ts"]
+    );
+
+    #[cfg(unix)]
+    {
+      use std::os::unix::fs::PermissionsExt;
+
+      let locked = dir.path().join("locked");
+      std::fs::create_dir(&locked).unwrap();
+      std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o000)).unwrap();
+      let is_readable = std::fs::read_dir(&locked).is_ok();
+
+      // Only directories that could hold a match are read.
+      assert_eq!(walk_paths(dir.path(), &["src/**/*.ts"]), ["src/a/x.ts"]);
+      if !is_readable {
+        let results: Vec<_> = walk(dir.path(), ["**/*.ts"]).collect();
+        assert_eq!(results.iter().filter(|result| result.is_err()).count(), 1);
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+      }
+
+      std::fs::set_permissions(&locked, std::fs::Permissions::from_mode(0o755)).unwrap();
+    }
+  }
+
+  #[test]
+  fn walk_in_parallel() {
+    let dir = tempfile::tempdir().unwrap();
+    for a in 0..8 {
+      for b in 0..8 {
+        let path = dir.path().join(format!("{a}/{b}"));
+        std::fs::create_dir_all(&path).unwrap();
+        for c in 0..4 {
+          std::fs::write(path.join(format!("{c}.txt")), "").unwrap();
+          std::fs::write(path.join(format!("{c}.rs")), "").unwrap();
+        }
+      }
+    }
+
+    for patterns in [
+      &["**/*.rs"][..],
+      &["1/**", "2/*/3.txt"],
+      &["*/[0-3]/*.{rs,txt}"],
+      &["**"],
+    ] {
+      let expected: Vec<_> = walk_paths(dir.path(), patterns);
+
+      let sorted: Vec<_> = walk_parallel(dir.path(), patterns)
+        .threads(4)
+        .sorted(true)
+        .into_iter()
+        .map(|entry| entry.unwrap().into_path())
+        .collect();
+      let mut paths = sorted.clone();
+      paths.sort();
+      assert_eq!(sorted, paths);
+
+      for threads in [1, 3, 16] {
+        let mut paths: Vec<_> = walk_parallel(dir.path(), patterns)
+          .threads(threads)
+          .into_iter()
+          .map(|entry| {
+            let entry = entry.unwrap();
+            let path = entry.path().to_str().unwrap().replace('\\', "/");
+            if entry.is_dir() {
+              path + "/"
+            } else {
+              path
+            }
+          })
+          .collect();
+        paths.sort();
+        assert_eq!(paths, expected);
+      }
+    }
+
+    let sorted: Vec<_> = walk_parallel(dir.path(), ["1/{2,3}/*.rs", "1/2"])
+      .sorted(true)
+      .into_iter()
+      .map(|entry| entry.unwrap().into_path())
+      .collect();
+    assert_eq!(
+      sorted,
+      [
+        "1/2", "1/2/0.rs", "1/2/1.rs", "1/2/2.rs", "1/2/3.rs", "1/3/0.rs", "1/3/1.rs", "1/3/2.rs",
+        "1/3/3.rs"
+      ]
+      .map(std::path::PathBuf::from)
+    );
+
+    let results: Vec<_> = walk_parallel(dir.path(), ["{"]).into_iter().collect();
+    assert_eq!(results.len(), 1);
+    assert_eq!(
+      results[0].as_ref().unwrap_err().kind(),
+      std::io::ErrorKind::InvalidInput
+    );
+  }
+
+  #[test]
+  fn walk_nested_bases() {
+    let dir = tempfile::tempdir().unwrap();
+    for path in ["a/c/x.txt", "a-b/y.txt", "a/b/z.txt"] {
+      let path = dir.path().join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, "").unwrap();
+    }
+
+    // `a-b` sorts between `a` and `a/c`, which is still walked only once along with `a`.
+    let patterns = ["a/**", "a-b/**", "a/c/**"];
+    assert_eq!(
+      walk_paths(dir.path(), &patterns),
+      ["a-b/y.txt", "a/b/", "a/b/z.txt", "a/c/", "a/c/x.txt"]
+    );
+
+    let sorted: Vec<_> = walk_parallel(dir.path(), patterns)
+      .sorted(true)
+      .into_iter()
+      .map(|entry| entry.unwrap().into_path())
+      .collect();
+    assert_eq!(
+      sorted,
+      ["a/b", "a/b/z.txt", "a/c", "a/c/x.txt", "a-b/y.txt"].map(std::path::PathBuf::from)
+    );
+
+    assert_eq!(
+      walk_paths(dir.path(), &["a/b/*", "a//b/**", "a/./c/*"]),
+      ["a/b/z.txt", "a/c/x.txt"]
+    );
+  }
+
+  #[test]
+  fn gitignore() {
+    use fast_glob::gitignore::{Gitignore, Match};
+
+    let gitignore = Gitignore::new(
+      "
+# Build output
+/target
+node_modules/
+*.log
+!important.log
+docs/**/*.html
+**/cache/**
+logs/*.tmp
+foo/**/bar
+\\#notes
+\\!bang
+trailing\\  
+spaces   
+{a,b}
+dir/
+!dir/keep/
+\\n
+"
+      .lines(),
+    );
+    assert_eq!(gitignore.len(), 16);
+
+    assert_eq!(gitignore.matched("target", true), Match::Ignore);
+    assert_eq!(gitignore.matched("target", false), Match::Ignore);
+    assert_eq!(gitignore.matched("src/target", true), Match::None);
+
+    assert_eq!(gitignore.matched("node_modules", true), Match::Ignore);
+    assert_eq!(gitignore.matched("a/b/node_modules", true), Match::Ignore);
+    assert_eq!(gitignore.matched("node_modules", false), Match::None);
+    assert_eq!(gitignore.matched("node_modules/x.js", false), Match::None);
+    assert_eq!(
+      gitignore.matched_path_or_any_parents("a/node_modules/x/y.js", false),
+      Match::Ignore
+    );
+
+    assert_eq!(gitignore.matched("debug.log", false), Match::Ignore);
+    assert_eq!(gitignore.matched("a/b/debug.log", false), Match::Ignore);
+    assert_eq!(
+      gitignore.matched("a/important.log", false),
+      Match::Whitelist
+    );
+    assert_eq!(gitignore.matched("debug.logs", false), Match::None);
+
+    assert_eq!(gitignore.matched("docs/a.html", false), Match::Ignore);
+    assert_eq!(gitignore.matched("docs/a/b/c.html", false), Match::Ignore);
+    assert_eq!(gitignore.matched("src/docs/a.html", false), Match::None);
+
+    assert_eq!(gitignore.matched("cache/a", false), Match::Ignore);
+    assert_eq!(gitignore.matched("x/cache/a/b", false), Match::Ignore);
+    assert_eq!(gitignore.matched("x/cache", true), Match::None);
+
+    assert_eq!(gitignore.matched("logs/a.tmp", false), Match::Ignore);
+    assert_eq!(gitignore.matched("logs/a/b.tmp", false), Match::None);
+    assert_eq!(gitignore.matched("x/logs/a.tmp", false), Match::None);
+
+    assert_eq!(gitignore.matched("foo/bar", false), Match::Ignore);
+    assert_eq!(gitignore.matched("foo/a/b/bar", false), Match::Ignore);
+
+    assert_eq!(gitignore.matched("#notes", false), Match::Ignore);
+    assert_eq!(gitignore.matched("!bang", false), Match::Ignore);
+    assert_eq!(gitignore.matched("trailing ", false), Match::Ignore);
+    assert_eq!(gitignore.matched("spaces", false), Match::Ignore);
+    assert_eq!(gitignore.matched("{a,b}", false), Match::Ignore);
+    assert_eq!(gitignore.matched("a", false), Match::None);
+    assert_eq!(gitignore.matched("n", false), Match::Ignore);
+
+    assert_eq!(gitignore.matched("dir", true), Match::Ignore);
+    assert_eq!(gitignore.matched("dir/keep", true), Match::Whitelist);
+    assert_eq!(
+      gitignore.matched_path_or_any_parents("dir/keep/a", false),
+      Match::Ignore
+    );
+    assert_eq!(
+      gitignore.matched_path_or_any_parents("src/main.rs", false),
+      Match::None
+    );
+
+    let gitignore = Gitignore::new(["*", "!*/", "!*.rs", "[a-"]);
+    assert_eq!(gitignore.len(), 4);
+    assert_eq!(gitignore.matched("a.txt", false), Match::Ignore);
+    assert_eq!(gitignore.matched("src", true), Match::Whitelist);
+    assert_eq!(gitignore.matched("src/a.rs", false), Match::Whitelist);
+    assert_eq!(gitignore.matched("[a-", false), Match::Ignore);
+
+    // A trailing `\` escapes nothing, so git never matches the pattern.
+    let gitignore = Gitignore::new(["foo\\", "!bar\\", "*\\"]);
+    assert_eq!(gitignore.len(), 3);
+    for path in ["foo\\", "foo", "bar\\", "x\\"] {
+      assert_eq!(gitignore.matched(path, false), Match::None);
+    }
+
+    let gitignore = Gitignore::new(["", "# comment", "   ", "!", "/"]);
+    assert!(gitignore.is_empty());
+  }
+
+  #[test]
+  fn walk_ignore_files() {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in [
+      (".gitignore", "target/\n*.log\n!keep.log\n"),
+      (".git/info/exclude", "secret.txt\n"),
+      ("src/.gitignore", "gen/\n!debug.log\n"),
+      ("src/.ignore", "*.tmp\n"),
+      ("docs/.custom", "*.md\n"),
+      ("app.log", ""),
+      ("keep.log", ""),
+      ("secret.txt", ""),
+      ("src/a.rs", ""),
+      ("src/gen/x.rs", ""),
+      ("src/debug.log", ""),
+      ("src/other.log", ""),
+      ("src/b.tmp", ""),
+      ("target/x.rs", ""),
+      ("target/debug/y.rs", ""),
+      ("docs/a.md", ""),
+      ("docs/b.rs", ""),
+      (".git/hooks/a.rs", ""),
+      ("sub/.git/info/exclude", ""),
+      ("sub/.git/b.rs", ""),
+    ] {
+      let path = dir.path().join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, contents).unwrap();
+    }
+
+    let paths = |walk: Walk| {
+      let mut paths: Vec<_> = walk
+        .map(|entry| entry.unwrap().path().to_str().unwrap().replace('\\', "/"))
+        .collect();
+      paths.sort();
+      paths
+    };
+    let pattern = ["**/*.{rs,log,txt,tmp,md}"];
+
+    assert_eq!(paths(walk(dir.path(), pattern)).len(), 14);
+    assert_eq!(
+      paths(walk(dir.path(), pattern).git_ignore(true)),
+      [
+        "docs/a.md",
+        "docs/b.rs",
+        "keep.log",
+        "src/a.rs",
+        "src/b.tmp",
+        "src/debug.log"
+      ]
+    );
+    assert_eq!(
+      paths(
+        walk(dir.path(), pattern)
+          .git_ignore(true)
+          .dot_ignore(true)
+          .custom_ignore_file(".custom")
+      ),
+      ["docs/b.rs", "keep.log", "src/a.rs", "src/debug.log"]
+    );
+    assert_eq!(paths(walk(dir.path(), pattern).dot_ignore(true)).len(), 13);
+
+    // Git's own directories aren't walked.
+    assert!(paths(walk(dir.path(), ["**"]).git_ignore(true))
+      .iter()
+      .all(|path| !path.split('/').any(|name| name == ".git")));
+    assert_eq!(
+      paths(walk(dir.path(), [".git/**", "sub/.git/*"]).git_ignore(true)),
+      Vec::<String>::new()
+    );
+    assert_eq!(
+      paths(walk(dir.path(), ["sub/.git/*"])),
+      ["sub/.git/b.rs", "sub/.git/info"]
+    );
+
+    // The ignore files above a static base still apply.
+    assert_eq!(
+      paths(walk(dir.path(), ["src/**/*.log"]).git_ignore(true)),
+      ["src/debug.log"]
+    );
+    assert_eq!(
+      paths(walk(dir.path(), ["target/debug/*"]).git_ignore(true)),
+      Vec::<String>::new()
+    );
+    assert_eq!(
+      paths(walk(dir.path(), ["target/debug/*"])),
+      ["target/debug/y.rs"]
+    );
+
+    let parallel: Vec<_> = walk_parallel(dir.path(), pattern)
+      .threads(3)
+      .sorted(true)
+      .git_ignore(true)
+      .dot_ignore(true)
+      .custom_ignore_file(".custom")
+      .into_iter()
+      .map(|entry| entry.unwrap().path().to_str().unwrap().replace('\\', "/"))
+      .collect();
+    assert_eq!(
+      parallel,
+      ["docs/b.rs", "keep.log", "src/a.rs", "src/debug.log"]
+    );
+  }
+
+  #[test]
+  fn walk_separators() {
+    let dir = tempfile::tempdir().unwrap();
+    for (path, contents) in [
+      ("a/.gitignore", "c/\n/b/x.txt\n"),
+      ("a/z.txt", ""),
+      ("a/b/x.txt", ""),
+      ("a/b/y.txt", ""),
+      ("a/b/c/w.txt", ""),
+    ] {
+      let path = dir.path().join(path);
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, contents).unwrap();
+    }
+
+    for separator in [Separator::Slash, Separator::Backslash, Separator::Both] {
+      let options = MatchOptions::new().separator(separator);
+      let paths = |patterns: &[&str], git_ignore: bool| {
+        let mut paths: Vec<_> = walk_with_options(dir.path(), patterns, options)
+          .git_ignore(git_ignore)
+          .map(|entry| entry.unwrap().path().to_str().unwrap().replace('\\', "/"))
+          .collect();
+        paths.sort();
+        paths
+      };
+
+      assert_eq!(paths(&["a/*.txt"], false), ["a/z.txt"], "{separator:?}");
+      assert_eq!(
+        paths(&["a/b/*.txt"], false),
+        ["a/b/x.txt", "a/b/y.txt"],
+        "{separator:?}"
+      );
+      assert_eq!(
+        paths(&["**/*.txt"], false),
+        ["a/b/c/w.txt", "a/b/x.txt", "a/b/y.txt", "a/z.txt"],
+        "{separator:?}"
+      );
+      assert_eq!(
+        paths(&["**/*.txt"], true),
+        ["a/b/y.txt", "a/z.txt"],
+        "{separator:?}"
+      );
+      assert_eq!(
+        paths(&["a/b/*/*"], true),
+        Vec::<String>::new(),
+        "{separator:?}"
+      );
+
+      let parallel: Vec<_> = walk_parallel_with_options(dir.path(), ["a/**/*.txt"], options)
+        .sorted(true)
+        .git_ignore(true)
+        .into_iter()
+        .map(|entry| entry.unwrap().path().to_str().unwrap().replace('\\', "/"))
+        .collect();
+      assert_eq!(parallel, ["a/b/y.txt", "a/z.txt"], "{separator:?}");
+    }
+  }
+
+  #[test]
+  fn glob_list() {
+    let list = GlobList::new(["src/**", "!src/**/*.test.ts", "src/keep.test.ts"]).unwrap();
+    assert_eq!(list.len(), 3);
+    assert!(list.is_match("src/index.ts"));
+    assert!(list.is_match("src/a/b.rs"));
+    assert!(!list.is_match("src/a.test.ts"));
+    assert!(!list.is_match("src/a/b.test.ts"));
+    assert!(list.is_match("src/keep.test.ts"));
+    assert!(!list.is_match("test/index.ts"));
+    assert_eq!(list.last_match("src/keep.test.ts"), Some(2));
+    assert_eq!(list.last_match("src/a.test.ts"), Some(1));
+    assert_eq!(list.last_match("src/a.ts"), Some(0));
+    assert_eq!(list.last_match("test/a.ts"), None);
+
+    // Earlier patterns only decide when no later one matches.
+    let list = GlobList::new(["!**/*.md", "docs/**"]).unwrap();
+    assert!(list.is_match("docs/README.md"));
+    assert!(!list.is_match("README.md"));
+
+    let list = GlobList::new(["**", "!!*.md", "!*.md", "!!README.md"]).unwrap();
+    assert!(list.is_match("README.md"));
+    assert!(!list.is_match("CHANGELOG.md"));
+    assert!(list.is_match("src/a.md"));
+
+    let list = GlobList::new(["!**/node_modules/**"]).unwrap();
+    assert!(!list.is_match("src/index.ts"));
+
+    let list = GlobList::with_options(
+      ["*.TS", "!*.D.TS"],
+      MatchOptions::new().case_insensitive(true),
+    )
+    .unwrap();
+    assert!(list.is_match("a.ts"));
+    assert!(!list.is_match("a.d.ts"));
+
+    let list = GlobList::with_options(["!(*.d).ts"], MatchOptions::new().extglob(true)).unwrap();
+    assert!(list.is_match("a.ts"));
+    assert!(!list.is_match("a.d.ts"));
+
+    assert!(GlobList::new(Vec::<&str>::new()).unwrap().is_empty());
+    assert_eq!(
+      GlobList::new(["src/**", "!src/[a"]).unwrap_err().kind(),
+      ErrorKind::UnclosedClass
+    );
+  }
+
+  /// Reads the Rust string literal at the start of `source`, returning its value and the
+  /// rest of the source.
+  fn read_literal(source: &str) -> Option<(String, &str)> {
+    if let Some(rest) = source.strip_prefix("r\"") {
+      let end = rest.find('"')?;
+      return Some((rest[..end].to_string(), &rest[end + 1..]));
+    }
+
+    let mut chars = source.strip_prefix('"')?.char_indices();
+    let mut value = String::new();
+    while let Some((index, c)) = chars.next() {
+      match c {
+        '"' => return Some((value, &source[index + 2..])),
+        '\\' => match chars.next()?.1 {
+          'n' => value.push('\n'),
+          't' => value.push('\t'),
+          'r' => value.push('\r'),
+          '0' => value.push('\0'),
+          'u' => {
+            let digits: String = chars
+              .by_ref()
+              .map(|(_, c)| c)
+              .skip(1)
+              .take_while(|&c| c != '}')
+              .collect();
+            value.push(char::from_u32(u32::from_str_radix(&digits, 16).ok()?)?);
+          }
+          c => value.push(c),
+        },
+        c => value.push(c),
+      }
+    }
+
+    None
+  }
+
+  /// Returns the pattern and path of every `glob_match` call of this file spelled with two
+  /// string literals.
+  fn corpus() -> Vec<(String, String)> {
+    let source = include_str!("test.rs");
+    let mut pairs = Vec::new();
+    for (index, _) in source.match_indices("glob_match(") {
+      let rest = source[index + "glob_match(".len()..].trim_start();
+      let Some((glob, rest)) = read_literal(rest) else {
+        continue;
+      };
+      let Some(rest) = rest.trim_start().strip_prefix(',') else {
+        continue;
+      };
+      if let Some((path, rest)) = read_literal(rest.trim_start()) {
+        if rest
+          .trim_start()
+          .trim_start_matches(',')
+          .trim_start()
+          .starts_with(')')
+        {
+          pairs.push((glob, path));
+        }
+      }
+    }
+
+    pairs
+  }
+
+  #[test]
+  fn to_regex() {
+    // Pairs sharing a pattern are matched against the same compiled regex.
+    let mut globs: Vec<(String, Vec<String>)> = Vec::new();
+    for (glob, path) in corpus() {
+      match globs.iter_mut().find(|(g, _)| *g == glob) {
+        Some((_, paths)) => paths.push(path),
+        None => globs.push((glob, vec![path])),
+      }
+    }
+    assert!(globs.len() > 500);
+
+    let extra: &[(&str, &[&str])] = &[
+      ("{-2..2}", &["-2", "-1", "0", "2", "-0", "02", "3"]),
+      ("{01..10..3}", &["01", "04", "10", "1", "4", "004"]),
+      ("x{a..e..2}", &["xa", "xc", "xb", "xA", "xC"]),
+      ("ΣΑΣ*", &["σας", "ΣΑΣ", "ςας", "sas"]),
+      ("[ıσ]", &["I", "i", "ı", "Σ", "ς"]),
+      ("[!ıσ]", &["I", "i", "ı", "Σ", "ς", "/"]),
+      ("a{,b/}*", &[".x", "a.x", "ab/.x", "ab/x", "x"]),
+      ("{a,b/}**/x", &["a/x", "a.x", "b/.a/x", "b/a/x", "b/x"]),
+      ("x*(a|b/c)d", &["xd", "xaab/cd", "xb/d", "x.d"]),
+      ("@([!a])", &["/", "b", "\\"]),
+      ("x?(a|b)/**/.y", &["x/.y", "xa/b/.y", "xab/.y"]),
+      ("*/+([.a])", &["x/.a", "x/a.", ".x/a"]),
+      ("a[[:alpha:]/]b", &["a/b", "acb", "a\\b"]),
+      ("a\\/b", &["a\\/b", "a/b"]),
+      ("*[!a]*", &["b/", "baa/", "a/"]),
+      ("*[ab]**/**/", &["ab", "ab/", "a/b/", "ab/c/"]),
+      ("+()x*(@())", &["x", "xx"]),
+    ];
+    for (glob, paths) in extra {
+      globs.push((
+        glob.to_string(),
+        paths.iter().map(|path| path.to_string()).collect(),
+      ));
+    }
+
+    let options = [
+      MatchOptions::new(),
+      MatchOptions::new().dot(false),
+      MatchOptions::new().literal_separator(false),
+      MatchOptions::new().literal_separator(false).dot(false),
+      MatchOptions::new().case_insensitive(true),
+      MatchOptions::new().extglob(true),
+      MatchOptions::new().extglob(true).dot(false),
+      MatchOptions::new().separator(Separator::Both),
+      MatchOptions::new().basename(true),
+      MatchOptions::new().basename(true).dot(false),
+      MatchOptions::new()
+        .backslash_escape(false)
+        .separator(Separator::Backslash),
+    ];
+
+    for options in options {
+      for (glob, paths) in &globs {
+        let compiled = match Glob::with_options(glob, options.utf8(true)) {
+          Ok(compiled) => compiled,
+          Err(_) => {
+            assert!(super::to_regex(glob, options).is_err(), "{glob:?}");
+            continue;
+          }
+        };
+
+        let regex = match super::to_regex(glob, options) {
+          Ok(regex) => regex,
+          Err(err) => {
+            assert_eq!(err.kind(), ErrorKind::Unsupported, "{glob:?}");
+            continue;
+          }
+        };
+
+        // Regexes without lookaround must work with the `regex` crate too.
+        let fancy = fancy_regex::Regex::new(&regex).unwrap();
+        let plain = (!regex.contains("(?!")).then(|| regex::Regex::new(&regex).unwrap());
+        for path in paths {
+          let expected = compiled.is_match(path);
+          if glob.is_ascii() && path.is_ascii() && options == MatchOptions::new() {
+            assert_eq!(glob_match(glob, path), expected, "{glob:?} {path:?}");
+          }
+
+          assert_eq!(
+            fancy.is_match(path).unwrap(),
+            expected,
+            "{glob:?} {path:?} {regex:?} {options:?}"
+          );
+          if let Some(plain) = &plain {
+            assert_eq!(
+              plain.is_match(path),
+              expected,
+              "{glob:?} {path:?} {regex:?}"
+            );
+          }
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn to_regex_random() {
+    // A xorshift generator, so that failures reproduce.
+    let mut state = 0x9e37_79b9_7f4a_7c15u64;
+    let mut random = |n: usize| {
+      state ^= state << 13;
+      state ^= state >> 7;
+      state ^= state << 17;
+      (state % n as u64) as usize
+    };
+
+    let atoms = [
+      "a",
+      "b",
+      "/",
+      "*",
+      "**",
+      "?",
+      "[ab]",
+      "[!a]",
+      "[/]",
+      "{",
+      "}",
+      ",",
+      "!",
+      ".",
+      "@(",
+      "*(",
+      "+(",
+      "?(",
+      ")",
+      "|",
+      "é",
+      "{a,b}",
+      "{1..3}",
+      "1",
+      "\\",
+      "\\*",
+      "[[:alpha:]]",
+      "A",
+    ];
+    let path_atoms = ["a", "b", "/", ".", "ab", "é", "1", "2", "A", "\\", "*"];
+    let options = [
+      MatchOptions::new(),
+      MatchOptions::new().dot(false),
+      MatchOptions::new().literal_separator(false),
+      MatchOptions::new().literal_separator(false).dot(false),
+      MatchOptions::new().case_insensitive(true),
+      MatchOptions::new().extglob(true),
+      MatchOptions::new().extglob(true).dot(false),
+      MatchOptions::new().separator(Separator::Both),
+      MatchOptions::new().basename(true).dot(false),
+      MatchOptions::new()
+        .backslash_escape(false)
+        .separator(Separator::Both)
+        .dot(false),
+    ];
+
+    for _ in 0..5000 {
+      let glob: String = (0..random(8)).map(|_| atoms[random(atoms.len())]).collect();
+      let options = options[random(options.len())];
+      let (Ok(compiled), Ok(regex)) = (
+        Glob::with_options(&glob, options.utf8(true)),
+        super::to_regex(&glob, options),
+      ) else {
+        continue;
+      };
+
+      let fancy = fancy_regex::Regex::new(&regex).unwrap();
+      for _ in 0..8 {
+        let path: String = (0..random(7))
+          .map(|_| path_atoms[random(path_atoms.len())])
+          .collect();
+        assert_eq!(
+          fancy.is_match(&path).unwrap(),
+          compiled.is_match(&path),
+          "{glob:?} {path:?} {regex:?} {options:?}"
+        );
+      }
+    }
+  }
+
+  #[test]
+  fn to_regex_output() {
+    let to_regex = |glob: &str| super::to_regex(glob, MatchOptions::new()).unwrap();
+
+    assert_eq!(to_regex("*.rs"), r"^[^/]*\.rs$");
+    assert_eq!(to_regex("src/**"), r"^src/[\s\S]*$");
+    assert_eq!(to_regex("**/a?.{js,ts}"), r"^(?:[^/]*/)*a[^/]\.(?:js|ts)$");
+    assert_eq!(to_regex("[!a-c.]x"), r"^[^a-c\x2e]x$");
+    assert_eq!(to_regex("file{1..3}"), r"^file(?:1|2|3)$");
+    assert_eq!(to_regex("!*.md"), r"^(?![^/]*\.md$)[\s\S]*$");
+    assert_eq!(to_regex("\\*(a)"), r"^\*\(a\)$");
+
+    let options = MatchOptions::new().dot(false);
+    assert_eq!(
+      super::to_regex("src/*", options).unwrap(),
+      r"^src/(?!\.)[^/]*$"
+    );
+
+    let options = MatchOptions::new().extglob(true);
+    assert_eq!(
+      super::to_regex("@(a|b)/+(x)", options).unwrap(),
+      r"^(?:a|b)/(?:x)+$"
+    );
+    let err = super::to_regex("src/!(*.d).ts", options).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert_eq!(err.span(), 4..6);
+    let err = super::to_regex("[!(]/*(a)/!(b)", options).unwrap_err();
+    assert_eq!(err.span(), 10..12);
+
+    let err = super::to_regex("a/{1..100000}", MatchOptions::new()).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::Unsupported);
+    assert_eq!(err.span(), 2..13);
+    assert_eq!(
+      super::to_regex("[a", MatchOptions::new())
+        .unwrap_err()
+        .kind(),
+      ErrorKind::UnclosedClass
+    );
+  }
+
+  #[test]
+  fn escape() {
+    let texts = [
+      "report[2024]{final}.txt",
+      "!important",
+      "!!a",
+      "a\\nb",
+      "\\",
+      "a\\",
+      "**",
+      "src/**/*.rs",
+      "{1..3}",
+      "{a,b}",
+      "[!a]",
+      "[[:alpha:]]",
+      "@(a|b)",
+      "!(a)",
+      "+(x)",
+      "?",
+      "a,b}",
+      "tab\tand\nnewline",
+      "café/ünïcode",
+      "",
+    ];
+
+    let options = [
+      MatchOptions::new(),
+      MatchOptions::new().extglob(true),
+      MatchOptions::new().case_insensitive(true).utf8(true),
+      MatchOptions::new().dot(false),
+    ];
+
+    for text in texts {
+      let pattern = super::escape(text);
+      assert!(glob_match(&pattern, text), "{pattern:?}");
+      assert!(!glob_match(&pattern, format!("{text}x")), "{pattern:?}");
+      assert!(Glob::new(&pattern).unwrap().is_literal(), "{pattern:?}");
+      for options in options {
+        let glob = Glob::with_options(&pattern, options).unwrap();
+        assert!(glob.is_match(text), "{pattern:?} {options:?}");
+      }
+
+      let pattern = escape_path(text);
+      assert!(glob_match(&pattern, text), "{pattern:?}");
+      assert!(!glob_match(&pattern, text.replace('/', "\\")) || !text.contains('/'));
+    }
+
+    assert_eq!(super::escape("a*b?c"), "a\\*b\\?c");
+    assert_eq!(super::escape("\\n"), "\\\\n");
+    assert!(!glob_match(super::escape("[ab]"), "a"));
+    assert!(!glob_match(super::escape("{a,b}"), "a"));
+    assert!(!glob_match(super::escape("!a"), "b"));
+
+    let path = b"bad\xff[utf8]/*";
+    assert!(glob_match(escape_path(path), path));
+    assert!(!glob_match(escape_path(path), b"bad\xff[utf8]/a"));
+    assert!(!glob_match(escape_path("a/b"), "a\\b"));
+  }
+
   #[test]
   fn not_paired_braces() {
     assert!(!glob_match("{a,}}", "a"));